@@ -1,4 +1,4 @@
-const { invoke } = globalThis.__TAURI__.core;
+const { invoke, Channel } = globalThis.__TAURI__.core;
 const { listen } = globalThis.__TAURI__.event;
 
 /**
@@ -7,53 +7,333 @@ const { listen } = globalThis.__TAURI__.event;
  */
 
 /**
- * The type of device, classified by its discovered mDNS service.
- * @typedef {'Bluesound'|'Volumio'|'SpotifyConnect'|'QobuzConnect'|'Generic'} DeviceType
+ * The type of device, classified by its discovered mDNS service, discriminated by `kind`.
+ * A `custom` type carries the label assigned by a custom classification rule on the plugin's `Builder`.
+ * @typedef {{ kind: 'bluesound'|'volumio'|'spotifyConnect'|'qobuzConnect'|'roon'|'chromecast'|'generic' } | { kind: 'custom', value: string }} DeviceType
+ */
+
+/**
+ * Why a scan ended: `timeout` when its duration elapsed, `user` when `stopScan` was called,
+ * `error` when it could not run, `quiet` when `scanUntilQuiet` saw the network settle,
+ * `maxLifetime` when it reached its `maxLifetimeSecs`, `count` instead of `timeout` when its
+ * duration elapsed after it reached its `maxDevices` cap, and `none` when no scan has ended yet.
+ * @typedef {'none'|'timeout'|'user'|'error'|'quiet'|'maxLifetime'|'count'} ScanEndReason
+ */
+
+/**
+ * A message streamed by `startScanWithChannel`: a found or updated device, or the end of the
+ * scan with why it ended. Nothing follows a `finished` message.
+ * @typedef {{ event: 'device', data: Device } | { event: 'finished', data: ScanEndReason }} ScanStreamMessage
+ */
+
+/**
+ * The error every command rejects with. Branch on `kind`; `message` is meant for humans.
+ * @typedef {object} ScanError
+ * @property {'daemonInit'|'browseFailed'|'notScanning'|'alreadyScanning'|'shutdown'|'invalidArgument'|'deviceNotFound'|'channel'|'registerFailed'} kind - What went wrong.
+ * @property {string} message - A human-readable description of the error.
  */
 
 /**
  * Represents a specific mDNS service discovered on a device.
+ * The SRV record's priority and weight aren't available from the mDNS library, so they're not included.
  * @typedef {object} DiscoveredService
  * @property {string} serviceType - The mDNS service type (e.g. `_http._tcp.local.`).
  * @property {number} port - The advertised port for the service.
  * @property {DeviceType} deviceType - Classification derived from the service.
  * @property {number} lastSeenMs - Milliseconds elapsed when this service was last observed.
+ * @property {boolean | null} reachable - Whether a TCP connection to the service's port succeeded, or `null` until checked. Only checked for scans started with `verifyReachability`.
+ * @property {Record<string, string>} txtProperties - The TXT record properties, e.g. `model` or `version`. Non-UTF-8 values are left out; properties without a value map to `""`.
+ * @property {string} displayName - A human-readable name for the service type, like `"Spotify Connect"` or `"Web Service"`, for showing to end users. The raw service type for types the plugin doesn't know.
+ * @property {string} fullname - The instance fullname the service was last resolved under, e.g. `"Living Room._spotify-connect._tcp.local."`, as advertised even with `clean_names`.
  */
 
 /**
  * Represents a device discovered on the local network.
  * @typedef {object} Device
  * @property {string} name - The advertised name of the device.
- * @property {string} ip - The IP address of the device.
+ * @property {string} ip - The IP address of the device, globally routable over private over link-local when it has several, and IPv4 over IPv6 among equally routable ones.
  * @property {number} discoveryTimeMs - Milliseconds elapsed before the first service on this device was discovered.
+ * @property {string} hostname - The resolvable hostname of the device, e.g. `"volumio.local."`, for follow-up DNS lookups or connections. Kept as advertised even with `clean_names`, and empty if none was advertised.
+ * @property {string[]} hostnames - The hostnames this device was advertised under, stripped of the trailing dot and `.local` suffix with `clean_names`.
+ * @property {string[]} addresses - The usable IP addresses this device advertised.
+ * @property {string[]} allIps - The usable IP addresses other than `ip`, most routable first.
+ * @property {string[]} additionalIps - The IPs of devices with the same name merged into this one. Only filled by scans started with `mergeByName`.
  * @property {DiscoveredService[]} services - The services discovered on this device.
+ * @property {string[]} tags - User-assigned tags, kept as the device's services are refreshed.
+ * @property {string} fingerprint - A stable identifier derived from the hostname, service types and stable TXT keys. Survives IP changes and app restarts.
+ * @property {boolean} online - Whether the device is currently advertising any service. Only ever `false` for scans started with `keepOfflineDevices`.
+ * @property {string | null} mac - The MAC address from the system's ARP table, like `"aa:bb:cc:dd:ee:ff"`. Only looked up for scans started with `resolveMac`, and `null` until found.
+ * @property {string | null} vendor - The vendor registered for the MAC address's prefix, like `"Sonos, Inc."`, from a table of common vendors embedded in the plugin. `null` until the MAC is found, and for prefixes the table doesn't know.
+ */
+
+/**
+ * The raw fields of a resolved service instance, as last received, for debugging classification.
+ * The SRV record's priority and weight aren't available from the mDNS library, so they're not included.
+ * @typedef {object} RawServiceRecord
+ * @property {string} fullname - The instance's fullname, e.g. `"Living Room._musc._tcp.local."`.
+ * @property {string} serviceType - The service type and domain, e.g. `"_musc._tcp.local."`.
+ * @property {string | null} subtype - The subtype the instance was found under, if any.
+ * @property {string} hostname - The hostname from the SRV record, before any cleaning.
+ * @property {number} port - The port from the SRV record.
+ * @property {string[]} addresses - Every advertised address, including the ones the scan configuration filters out.
+ * @property {Record<string, string | null>} txt - Every TXT record property. Flags without a value are `null`, and values that aren't valid UTF-8 are decoded lossily.
+ * @property {number} resolvedAtMs - Milliseconds from the start of the scan when the instance was last resolved.
+ */
+
+/**
+ * A device decoded from the compact binary form returned by `getDevicesPacked`.
+ * Carries only the core fields of a {@link Device}.
+ * @typedef {object} PackedDevice
+ * @property {string} name - The advertised name of the device.
+ * @property {string} ip - The IP address of the device.
+ * @property {number} discoveryTimeMs - Milliseconds elapsed before the first service on this device was discovered.
+ * @property {{ serviceType: string, port: number, deviceType: DeviceType, lastSeenMs: number }[]} services - The services discovered on this device.
+ */
+
+/**
+ * Options controlling how a LAN scan behaves. Every field is optional.
+ * @typedef {object} ScanConfig
+ * @property {boolean} [keepZeroPort] - Keep services advertised on port 0 instead of skipping them. Defaults to `false`.
+ * @property {boolean} [collapseAliases] - Merge services from alias hostnames with overlapping addresses into one device. Defaults to `false`.
+ * @property {boolean} [trackIpChanges] - Move a known device that reappears at a new IP instead of adding a duplicate. Defaults to `false`.
+ * @property {number | null} [perTypeLimit] - The maximum number of devices collected per service type. Unbounded by default.
+ * @property {number | null} [maxDevices] - The maximum number of devices collected, counted by unique IP. Unbounded by default.
+ * @property {boolean} [reliable] - Re-browse on an exponential back-off for the whole scan. More traffic, higher catch rate. Defaults to `false`.
+ * @property {string | null} [instancePrefix] - Only keep instances whose name starts with this prefix, e.g. `"NODE-"`. Keeps every instance by default.
+ * @property {boolean} [keepOfflineDevices] - Keep a device that loses its last service, marked `online: false`, instead of deleting it. Defaults to `false`.
+ * @property {number | null} [maxLifetimeSecs] - An upper bound on how long the scan may run, however it was started. Once reached it stops with reason `maxLifetime`. Unbounded by default.
+ * @property {string[]} [ignoreGenericServiceTypes] - Service types whose services are dropped when they'd classify as `generic`. Empty by default.
+ * @property {string[]} [serviceTypes] - Extra service types to browse, e.g. `"_sonos._tcp.local."` or the subtype `"_printer._sub._http._tcp.local."`. Each must end with `.local.`. Empty by default.
+ * @property {boolean} [replaceServiceTypes] - Browse only `serviceTypes` instead of adding them to the built-in ones. Defaults to `false`.
+ * @property {boolean} [preservePrevious] - Keep the devices of previous scans and merge new services into them instead of clearing the list. `discoveryTimeMs` is then the lowest across scans. Defaults to `false`.
+ * @property {boolean} [continuous] - Scan until `stopScan` is called instead of stopping after the scan duration. No `scan-tick` events fire. Defaults to `false`.
+ * @property {number | null} [heartbeatIntervalSecs] - How often a continuous scan emits `device-heartbeat` with the current counts, in seconds. Defaults to `null`, emitting none.
+ * @property {string[]} [interfaces] - The network interfaces to scan on, by name (e.g. `"en0"`) or IP address. Scans on every interface by default.
+ * @property {boolean} [mergeByName] - Merge a device into a known one with the same name at another IP, listing that IP in `additionalIps`. Defaults to `false`.
+ * @property {boolean} [verifyReachability] - Try a TCP connection to each newly resolved service and record the result in its `reachable`. Defaults to `false`.
+ * @property {number | null} [reachabilityTimeoutMs] - How long a reachability check waits for the connection, in milliseconds. Defaults to 500.
+ * @property {boolean} [resolveMac] - Look up each IPv4 device's MAC address in the system's ARP table and record it in its `mac`, along with the vendor in `vendor`. Defaults to `false`.
+ * @property {number | null} [queryIntervalMs] - Re-query every service type at this interval, in milliseconds, for faster discovery at the cost of more multicast traffic. Each re-query restarts the type's browse, as the mDNS daemon's own query interval can't be set. Values below 1000 are raised to 1000. Defaults to `null`, sending only the mDNS daemon's own queries.
+ * @property {'shutdown'|'idle'} [onTimeout] - What happens once the scan duration elapses: `"shutdown"` frees the mDNS daemon, `"idle"` keeps it alive until `stopScan` so follow-ups like `rescanDevice` are instant. Defaults to `"shutdown"`.
+ * @property {boolean} [allowReresolution] - Process every resolution of a service instead of only the first per device and service type, so port changes and `lastSeenMs` stay fresh. `new-device` still only fires for new devices. Defaults to `false`.
+ * @property {number | null} [deviceEmitIntervalMs] - The shortest time between two `new-device` or `device-updated` events for the same device, in milliseconds. Updates arriving sooner are coalesced into one event with the latest state. Defaults to 250; 0 emits every update.
+ * @property {boolean} [allowLinkLocal] - Keep link-local IPv4 and IPv6 addresses. IPv6 ones carry their zone, e.g. `"fe80::1%en0"`. Defaults to `false`.
+ * @property {boolean} [includeIpv6] - Keep global and unique-local IPv6 addresses. A dual-stack device is keyed by its most routable address, IPv4 winning only among equally routable ones, like `ip`. Defaults to `false`.
+ * @property {'v4'|'v6'|'both'} [ipVersion] - The IP version the mDNS daemon binds to and addresses are kept for. `"v6"` keeps IPv6 addresses as with `includeIpv6`. Defaults to `"both"`.
+ * @property {string[]} [exclude] - IP addresses and CIDR subnets, like `"192.168.1.1"` or `"10.0.0.0/24"`, whose devices are ignored. Defaults to `[]`.
+ * @property {string[]} [includeOnly] - CIDR subnets, like `"10.20.0.0/16"`, outside of which addresses are ignored. Applied before `exclude`. Defaults to `[]`, keeping every address.
+ */
+
+/**
+ * The configuration `startScan` uses when called without one, delivered by `getDefaultConfig`,
+ * along with the duration a scan started without one runs for.
+ * @typedef {Required<ScanConfig> & { durationSecs: number | null }} DefaultScanConfig
+ */
+
+/**
+ * The effective settings of a scan, delivered by `getCurrentScanConfig`.
+ * @typedef {object} CurrentScanConfig
+ * @property {boolean} active - Whether a scan is running, so these are its settings rather than the defaults.
+ * @property {number | null} durationSecs - How long the scan runs before it stops automatically, in seconds, or `null` for a continuous scan.
+ * @property {string[]} serviceTypes - The service types browsed.
+ * @property {boolean} includeIpv6 - Whether IPv6 addresses are kept.
+ * @property {boolean} continuous - Whether the scan runs until it is stopped.
+ */
+
+/**
+ * A summary of a whole scan, delivered by `onScanComplete` and `getLastScanReport`.
+ * @typedef {object} ScanReport
+ * @property {number} deviceCount - The number of devices discovered.
+ * @property {number} serviceCount - The number of services discovered, across all devices.
+ * @property {Record<string, number>} devicesPerType - The number of devices with at least one service of each type, keyed by its `kind`, or its label for a custom type.
+ * @property {Record<string, number>} devicesPerServiceType - The number of devices exposing each service type.
+ * @property {number | null} fastestDiscoveryMs - The lowest `discoveryTimeMs` among the devices, or `null` if none was discovered.
+ * @property {number | null} slowestDiscoveryMs - The highest `discoveryTimeMs` among the devices, or `null` if none was discovered.
+ * @property {number} durationMs - How long the scan ran, in milliseconds.
+ * @property {ScanEndReason} reason - Why the scan ended.
+ */
+
+/**
+ * Counters accumulated across the scans since the app started, delivered by `getScanStats`.
+ * @typedef {object} ScanStats
+ * @property {number} scansStarted - The number of scans that started.
+ * @property {number} devicesDiscovered - The number of devices added to the device list, counting rediscovered devices again.
+ * @property {number} totalScanTimeMs - How long scans have run in total, in milliseconds, including the running scan.
+ */
+
+/**
+ * Aggregates of `discoveryTimeMs` across the known devices, delivered by `getDiscoveryLatencyStats`.
+ * @typedef {object} DiscoveryLatencyStats
+ * @property {number} deviceCount - The number of devices the aggregates cover.
+ * @property {number} minMs - The lowest discovery time.
+ * @property {number} maxMs - The highest discovery time.
+ * @property {number} meanMs - The mean discovery time.
+ * @property {number} p50Ms - The median discovery time.
+ * @property {number} p95Ms - The 95th percentile of the discovery times.
+ */
+
+/**
+ * The details of a scan that just started, delivered by `onScanStarted`.
+ * @typedef {object} ScanStarted
+ * @property {number | null} durationSecs - How long the scan runs before it stops automatically, in seconds, after the minimum scan duration is applied, or `null` for a continuous scan.
+ * @property {string[]} serviceTypes - The service types being browsed.
+ */
+
+/**
+ * A service type a scan couldn't browse for, delivered by `onBrowseError`.
+ * @typedef {object} BrowseError
+ * @property {string} serviceType - The service type that couldn't be browsed.
+ * @property {string} message - The error the mDNS daemon reported.
+ * @property {boolean} anySucceeded - Whether browsing for at least one other service type of the scan succeeded.
+ */
+
+/**
+ * Why the mDNS daemon couldn't be created, delivered by `onDaemonError`.
+ * @typedef {object} DaemonError
+ * @property {string} message - The error of the last attempt.
+ * @property {'portInUse'|'permissionDenied'|'unknown'} cause - The likely cause, inferred from the error.
+ * @property {number} attempts - How many times creating the daemon was attempted.
+ */
+
+/**
+ * Criteria for `queryDevices`. Every provided criterion must match; a list matches when any of its entries does.
+ * @typedef {object} DeviceFilter
+ * @property {DeviceType[]} [types] - Device types, one of which a service on the device must be classified as.
+ * @property {string[]} [serviceTypes] - Service types, one of which the device must expose.
+ * @property {string} [nameContains] - A case-insensitive substring of the device's name.
+ * @property {string} [subnet] - An IPv4 or IPv6 subnet in CIDR notation, e.g. `"192.168.1.0/24"` or `"fd00::/8"`, one of the device's addresses must fall in.
+ * @property {number} [port] - A port a service on the device must be advertised on.
+ * @property {number} [seenSinceMs] - The time in ms from the start of the scan since which a service on the device must have been seen.
  */
 
 /**
  * The RAWDOG API for the LAN Scanner plugin.
  * This is attached to `globalThis.__TAURI__.lanScanner` for easy access from the frontend.
  * @typedef {{ 
+ *  setEventPrefix: typeof setEventPrefix,
  *  startScan: typeof startScan,
+ *  startScanWithChannel: typeof startScanWithChannel,
  *  stopScan: typeof stopScan,
+ *  forceShutdown: typeof forceShutdown,
  *  isScanning: typeof isScanning,
+ *  pauseScan: typeof pauseScan,
+ *  resumeScan: typeof resumeScan,
+ *  isPaused: typeof isPaused,
+ *  getLastScanReason: typeof getLastScanReason,
+ *  getLastScanReport: typeof getLastScanReport,
+ *  getScanStats: typeof getScanStats,
+ *  getDiscoveryLatencyStats: typeof getDiscoveryLatencyStats,
+ *  scanUntilQuiet: typeof scanUntilQuiet,
+ *  scanOnce: typeof scanOnce,
  *  getDiscoveredDevices: typeof getDiscoveredDevices,
+ *  getDevicesWithFreshness: typeof getDevicesWithFreshness,
+ *  clearDevices: typeof clearDevices,
+ *  replayDevices: typeof replayDevices,
+ *  getDeviceByIp: typeof getDeviceByIp,
+ *  getRawRecords: typeof getRawRecords,
+ *  getDevicesByPort: typeof getDevicesByPort,
+ *  getDevicesByType: typeof getDevicesByType,
+ *  hasDeviceOfServiceType: typeof hasDeviceOfServiceType,
+ *  getTypeCounts: typeof getTypeCounts,
+ *  queryDevices: typeof queryDevices,
+ *  devicesWithServiceNotOther: typeof devicesWithServiceNotOther,
+ *  getMultiserviceDevices: typeof getMultiserviceDevices,
+ *  getNameConflicts: typeof getNameConflicts,
+ *  getDefaultConfig: typeof getDefaultConfig,
+ *  getCurrentScanConfig: typeof getCurrentScanConfig,
+ *  getScanTimeRemaining: typeof getScanTimeRemaining,
+ *  getDevicesPacked: typeof getDevicesPacked,
+ *  getFastestDeviceOfType: typeof getFastestDeviceOfType,
+ *  getDeviceByFingerprint: typeof getDeviceByFingerprint,
+ *  addDeviceTag: typeof addDeviceTag,
+ *  removeDeviceTag: typeof removeDeviceTag,
+ *  resolveInstance: typeof resolveInstance,
+ *  resolveFullname: typeof resolveFullname,
+ *  discoverServiceTypes: typeof discoverServiceTypes,
+ *  getMemoryEstimate: typeof getMemoryEstimate,
+ *  watchDevice: typeof watchDevice,
+ *  reclassifyAll: typeof reclassifyAll,
+ *  pruneStaleDevices: typeof pruneStaleDevices,
+ *  decodePackedDevices: typeof decodePackedDevices,
+ *  boostScan: typeof boostScan,
+ *  rescanDevice: typeof rescanDevice,
+ *  registerService: typeof registerService,
+ *  unregisterService: typeof unregisterService,
+ *  onDevicesCleared: typeof onDevicesCleared,
  *  onNewDevice: typeof onNewDevice,
+ *  onDeviceSeen: typeof onDeviceSeen,
+ *  onTypeCounts: typeof onTypeCounts,
+ *  onDeviceUpdated: typeof onDeviceUpdated,
+ *  onDeviceIpChanged: typeof onDeviceIpChanged,
+ *  onDeviceRemoved: typeof onDeviceRemoved,
+ *  onScanStarted: typeof onScanStarted,
+ *  onDaemonError: typeof onDaemonError,
+ *  onBrowseError: typeof onBrowseError,
+ *  onDeviceCapReached: typeof onDeviceCapReached,
+ *  onDeviceHeartbeat: typeof onDeviceHeartbeat,
+ *  onScanComplete: typeof onScanComplete,
  *  onScanStopped: typeof onScanStopped,
- *  onScanTick: typeof onScanTick
+ *  onScanPaused: typeof onScanPaused,
+ *  onScanResumed: typeof onScanResumed,
+ *  onScanTick: typeof onScanTick,
+ *  onScanProgress: typeof onScanProgress,
+ *  onScanBoosted: typeof onScanBoosted,
+ *  onScanBoostEnded: typeof onScanBoostEnded
  * }} LanScannerPlugin
  */
 
+let eventPrefix = "";
+
+/**
+ * Returns the name an event is emitted under, given the prefix set with `setEventPrefix`.
+ *
+ * @param {string} name - The bare event name, e.g. `"new-device"`.
+ * @returns {string} The prefixed event name.
+ */
+function eventName(name) {
+	return eventPrefix ? `${eventPrefix}:${name}` : name;
+}
+
+/**
+ * Sets the prefix the plugin was configured to emit events under with `init_with_prefix` or the
+ * builder's `event_prefix`, so the `on*` listeners subscribe to the prefixed names. Call it before
+ * registering any listener.
+ *
+ * @param {string} prefix - The prefix, e.g. `"lan"`, or `""` for the bare names.
+ * @example
+ * scanner.setEventPrefix("lan");
+ * await scanner.onNewDevice(addDevice); // listens for `lan:new-device`
+ */
+function setEventPrefix(prefix) {
+	eventPrefix = prefix;
+}
+
 /**
  * Starts the mDNS service discovery scan on the local network.
- * The scan runs for 30 seconds and then stops automatically.
+ * The scan runs for `durationSecs` seconds, 30 by default, and then stops automatically.
  *
- * @returns {Promise<void>} A promise that resolves when the scan has been initiated.
+ * @param {ScanConfig} [config] - Optional settings for this scan.
+ * @param {number} [durationSecs] - How long the scan runs, in seconds. Raised to the minimum scan duration, 3 seconds unless the app sets another. Defaults to 30.
+ * @param {string} [scanId] - An id carried by every event of this scan, to tell apart scans started from different places. A UUID is generated if omitted.
+ * @param {number} [startDelayMs] - How long to wait, in milliseconds, before the daemon is created and browsing begins. The scan counts as running meanwhile, and stopping it cancels it.
+ * @returns {Promise<string>} A promise that resolves with the scan's id once the scan has been initiated, or with the running scan's id if one is already in progress.
  * @example
- * await scanner.startScan();
- * console.log("Scan started!");
+ * const scanId = await scanner.startScan();
+ * console.log(`Scan ${scanId} started!`);
+ * @example
+ * // A quick 5-second scan on startup
+ * await scanner.startScan(undefined, 5);
+ * @example
+ * // Give the network interfaces two seconds to come up on launch
+ * await scanner.startScan(undefined, undefined, undefined, 2000);
  */
-async function startScan() {
-	await invoke("plugin:lan-scanner|start_scan");
+async function startScan(config, durationSecs, scanId, startDelayMs) {
+	return await invoke("plugin:lan-scanner|start_scan", {
+		config,
+		durationSecs,
+		scanId,
+		startDelayMs,
+	});
 }
 
 /**
@@ -68,6 +348,125 @@ async function stopScan() {
 	await invoke("plugin:lan-scanner|stop_scan");
 }
 
+/**
+ * Starts a scan like `startScan`, streaming each found or updated device to the callback over a
+ * Tauri channel instead of requiring event listeners. The callback then receives a `finished`
+ * message once the scan stops. Rejects with an `alreadyScanning` error if a scan is running.
+ *
+ * @param {(message: ScanStreamMessage) => void} callback - The function to call with each message.
+ * @param {ScanConfig} [config] - Optional settings for this scan.
+ * @param {number} [durationSecs] - How long the scan runs, in seconds. Raised to the minimum scan duration, 3 seconds unless the app sets another. Defaults to 30.
+ * @param {string} [scanId] - An id carried by every event of this scan. A UUID is generated if omitted.
+ * @returns {Promise<string>} A promise that resolves with the scan's id once the scan has been initiated.
+ * @example
+ * await scanner.startScanWithChannel((message) => {
+ *   if (message.event === "device") upsertDevice(message.data);
+ *   else showScanEnded(message.data);
+ * });
+ */
+async function startScanWithChannel(callback, config, durationSecs, scanId) {
+	const channel = new Channel();
+	channel.onmessage = callback;
+	return await invoke("plugin:lan-scanner|start_scan_with_channel", {
+		channel,
+		config,
+		durationSecs,
+		scanId,
+	});
+}
+
+/**
+ * Tears the scanner down even if it doesn't consider a scan to be running, as a recovery hatch
+ * for a wedged scanner whose `stopScan` does nothing while devices keep arriving.
+ * Emits `scan-stopped` with `"user"`. Calling it again is harmless.
+ * Requires the opt-in `lan-scanner:allow-force-shutdown` permission.
+ *
+ * @returns {Promise<void>} A promise that resolves once the scanner has been shut down.
+ * @example
+ * await scanner.forceShutdown();
+ */
+async function forceShutdown() {
+	await invoke("plugin:lan-scanner|force_shutdown");
+}
+
+/**
+ * Retrieves why the last scan ended, so a view mounted after the fact can render the right state.
+ *
+ * @returns {Promise<ScanEndReason>} A promise that resolves with the reason, or `none` if no scan has ended yet.
+ * @example
+ * if ((await scanner.getLastScanReason()) === "timeout") showRescanHint();
+ */
+async function getLastScanReason() {
+	return await invoke("plugin:lan-scanner|get_last_scan_reason");
+}
+
+/**
+ * Retrieves the summary of the last scan that ran, however it ended. For a scan that ran its full duration, it's the one delivered by `onScanComplete`.
+ *
+ * @returns {Promise<ScanReport | null>} A promise that resolves with the report, or `null` if no scan has run yet.
+ * @example
+ * const report = await scanner.getLastScanReport();
+ * if (report) console.log(`${report.deviceCount} devices in ${report.durationMs}ms`);
+ */
+async function getLastScanReport() {
+	return await invoke("plugin:lan-scanner|get_last_scan_report");
+}
+
+/**
+ * Retrieves the counters accumulated across the scans since the app started, for a diagnostics panel.
+ *
+ * @returns {Promise<ScanStats>} A promise that resolves with the counters.
+ * @example
+ * const stats = await scanner.getScanStats();
+ * console.log(`${stats.scansStarted} scans, ${stats.devicesDiscovered} devices`);
+ */
+async function getScanStats() {
+	return await invoke("plugin:lan-scanner|get_scan_stats");
+}
+
+/**
+ * Retrieves the min, max, mean, median and 95th percentile of `discoveryTimeMs` across the known
+ * devices, for tracking discovery speed across app versions.
+ *
+ * @returns {Promise<DiscoveryLatencyStats | null>} A promise that resolves with the aggregates, or `null` if no device is known.
+ * @example
+ * const latency = await scanner.getDiscoveryLatencyStats();
+ * if (latency) console.log(`p95 ${latency.p95Ms}ms over ${latency.deviceCount} devices`);
+ */
+async function getDiscoveryLatencyStats() {
+	return await invoke("plugin:lan-scanner|get_discovery_latency_stats");
+}
+
+/**
+ * Runs a scan until the network goes quiet and resolves with the devices found.
+ * The scan stops once no new service has been discovered for `quietSecs` seconds,
+ * or after `maxSecs` seconds, whichever comes first.
+ *
+ * @param {number} quietSecs - How long the network must stay quiet before the scan stops.
+ * @param {number} maxSecs - The maximum time to wait, in seconds.
+ * @returns {Promise<Device[]>} A promise that resolves with the discovered devices once the scan has stopped.
+ * @example
+ * const devices = await scanner.scanUntilQuiet(3, 15);
+ * console.log(`Onboarding found ${devices.length} devices`);
+ */
+async function scanUntilQuiet(quietSecs, maxSecs) {
+	return await invoke("plugin:lan-scanner|scan_until_quiet", { quietSecs, maxSecs });
+}
+
+/**
+ * Scans for `durationSecs` seconds and resolves with the devices found, for flows that just want
+ * the list. Events still fire during the scan for any listening UI.
+ *
+ * @param {number} durationSecs - How long to scan, in seconds.
+ * @param {string[]} [serviceTypes] - The service types to browse instead of the built-in ones.
+ * @returns {Promise<Device[]>} A promise that resolves with the devices found once the scan has stopped. Rejects with `alreadyScanning` if a scan is running.
+ * @example
+ * const speakers = await scanner.scanOnce(10, ["_raop._tcp.local."]);
+ */
+async function scanOnce(durationSecs, serviceTypes) {
+	return await invoke("plugin:lan-scanner|scan_once", { durationSecs, serviceTypes });
+}
+
 /**
  * Checks if a scan is currently in progress.
  *
@@ -81,22 +480,589 @@ async function isScanning() {
 }
 
 /**
- * Retrieves the list of all devices discovered since the scan started.
+ * Pauses the running scan, e.g. while the user is on a bandwidth-sensitive screen. The mDNS
+ * daemon is shut down and the countdown frozen, while the discovered devices are kept.
+ * `isScanning` keeps resolving with `true` while paused. Pausing a paused scan does nothing.
  *
+ * @returns {Promise<void>} A promise that resolves once the scan is paused. Rejects if no scan is running.
+ * @example
+ * await scanner.pauseScan();
+ */
+async function pauseScan() {
+	await invoke("plugin:lan-scanner|pause_scan");
+}
+
+/**
+ * Resumes a paused scan, continuing its countdown where it was frozen. Resuming a scan that
+ * isn't paused does nothing.
+ *
+ * @returns {Promise<void>} A promise that resolves once the scan is browsing again. Rejects if no scan is running or the mDNS daemon can't be created.
+ * @example
+ * await scanner.resumeScan();
+ */
+async function resumeScan() {
+	await invoke("plugin:lan-scanner|resume_scan");
+}
+
+/**
+ * Checks if the running scan is paused.
+ *
+ * @returns {Promise<boolean>} A promise that resolves with `true` if the scan is paused, otherwise `false`.
+ * @example
+ * const paused = await scanner.isPaused();
+ */
+async function isPaused() {
+	return await invoke("plugin:lan-scanner|is_paused");
+}
+
+/**
+ * Retrieves the list of all devices discovered since the scan started, in a stable order.
+ *
+ * @param {'discoveryTime'|'name'|'ip'} [sortBy] - The order of the devices: earliest discovered first (the default), by name case-insensitively, or by IP numerically.
  * @returns {Promise<Device[]>} A promise that resolves with an array of discovered devices.
  * @example
  * const devices = await scanner.getDiscoveredDevices();
  * devices.forEach(device => console.log(`Found: ${device.name} at ${device.ip}`));
  */
-async function getDiscoveredDevices() {
-	return await invoke("plugin:lan-scanner|get_discovered_devices");
+async function getDiscoveredDevices(sortBy) {
+	return await invoke("plugin:lan-scanner|get_discovered_devices", { sortBy });
+}
+
+/**
+ * Retrieves the discovered devices, earliest discovered first, each flagged by whether it was seen recently.
+ *
+ * @param {number} staleAfterMs - How long, in milliseconds, a device may go unseen before it is flagged stale.
+ * @returns {Promise<Array<Device & { stale: boolean }>>} A promise that resolves with the devices, each with a `stale` flag.
+ * @example
+ * const devices = await scanner.getDevicesWithFreshness(30000);
+ * devices.forEach(device => console.log(`${device.name}: ${device.stale ? "stale" : "fresh"}`));
+ */
+async function getDevicesWithFreshness(staleAfterMs) {
+	return await invoke("plugin:lan-scanner|get_devices_with_freshness", { staleAfterMs });
+}
+
+/**
+ * Empties the discovered-devices list without starting a scan, and fires `devices-cleared`.
+ * Safe to call whether or not a scan is running; a running scan keeps going undisturbed.
+ *
+ * @returns {Promise<void>} A promise that resolves once the list is cleared.
+ * @example
+ * await scanner.clearDevices();
+ */
+async function clearDevices() {
+	return await invoke("plugin:lan-scanner|clear_devices");
+}
+
+/**
+ * Fires `new-device` again for every known device, earliest discovered first, so a window opened
+ * mid-scan can populate its `onNewDevice` listener the same way it handles live discovery.
+ *
+ * @returns {Promise<void>} A promise that resolves once every device was emitted.
+ * @example
+ * await scanner.onNewDevice(addDeviceToList);
+ * await scanner.replayDevices();
+ */
+async function replayDevices() {
+	await invoke("plugin:lan-scanner|replay_devices");
+}
+
+/**
+ * Retrieves a single discovered device by its IP address, e.g. the one a `new-device` event reported.
+ *
+ * @param {string} ip - The `ip` of the device.
+ * @returns {Promise<Device | null>} A promise that resolves with the device, or `null` if none is known at that IP.
+ * @example
+ * const device = await scanner.getDeviceByIp("192.168.1.42");
+ * if (device) showDetails(device);
+ */
+async function getDeviceByIp(ip) {
+	return await invoke("plugin:lan-scanner|get_device_by_ip", { ip });
+}
+
+/**
+ * Retrieves the raw fields of the last resolution of each service instance on a device, for
+ * diagnosing why it's classified the way it is.
+ *
+ * @param {string} ip - The `ip` of the device.
+ * @returns {Promise<RawServiceRecord[]>} A promise that resolves with the records, sorted by fullname. Rejects with a `deviceNotFound` error if no device is known at that IP.
+ * @example
+ * console.table(await scanner.getRawRecords("192.168.1.42"));
+ */
+async function getRawRecords(ip) {
+	return await invoke("plugin:lan-scanner|get_raw_records", { ip });
+}
+
+/**
+ * Retrieves the configuration a scan uses when `startScan` is called without one, reflecting the
+ * builder's `default_config`. Use it to reset a settings UI to the plugin's defaults instead of
+ * duplicating them. `serviceTypes` lists every browsed type, built-in ones included, with
+ * `replaceServiceTypes` set, and `durationSecs` is the effective duration of a scan started
+ * without one, or `null` for a continuous configuration.
+ *
+ * @returns {Promise<DefaultScanConfig>} A promise that resolves with the default scan configuration.
+ * @example
+ * const { durationSecs, ...config } = await scanner.getDefaultConfig();
+ * await scanner.startScan(config, durationSecs ?? undefined);
+ */
+async function getDefaultConfig() {
+	return await invoke("plugin:lan-scanner|get_default_config");
+}
+
+/**
+ * Retrieves the effective settings of the running scan, or those `startScan` would use without
+ * arguments when no scan is running. Handy for a debug overlay.
+ *
+ * @returns {Promise<CurrentScanConfig>} A promise that resolves with the effective settings.
+ * @example
+ * const { active, serviceTypes } = await scanner.getCurrentScanConfig();
+ */
+async function getCurrentScanConfig() {
+	return await invoke("plugin:lan-scanner|get_current_scan_config");
+}
+
+/**
+ * Retrieves the seconds left before the running scan stops, rounded up, computed from when the
+ * scan started and its duration, so a view mounting mid-scan can render its countdown right away.
+ *
+ * @returns {Promise<number | null>} A promise that resolves with the seconds left, or `null` if no scan is running, it is continuous, or it is waiting out its start delay.
+ * @example
+ * const secondsLeft = await scanner.getScanTimeRemaining();
+ * if (secondsLeft !== null) renderCountdown(secondsLeft);
+ */
+async function getScanTimeRemaining() {
+	return await invoke("plugin:lan-scanner|get_scan_time_remaining");
+}
+
+/**
+ * Retrieves the discovered devices that advertise at least one service on the given port.
+ *
+ * @param {number} port - The port to match against each device's services.
+ * @returns {Promise<Device[]>} A promise that resolves with the matching devices.
+ * @example
+ * const webUis = await scanner.getDevicesByPort(80);
+ * webUis.forEach(device => console.log(`Web UI: http://${device.ip}`));
+ */
+async function getDevicesByPort(port) {
+	return await invoke("plugin:lan-scanner|get_devices_by_port", { port });
+}
+
+/**
+ * Retrieves the discovered devices with at least one service classified as the given type.
+ *
+ * @param {DeviceType} deviceType - The type to match, e.g. `{ kind: "spotifyConnect" }`, or `{ kind: "custom", value: "myBrand" }` for a custom classification label.
+ * @returns {Promise<Device[]>} A promise that resolves with the matching devices.
+ * @example
+ * const spotifyDevices = await scanner.getDevicesByType({ kind: "spotifyConnect" });
+ */
+async function getDevicesByType(deviceType) {
+	return await invoke("plugin:lan-scanner|get_devices_by_type", { deviceType });
+}
+
+/**
+ * Checks whether any discovered device exposes a service of the given type, a cheap predicate
+ * for conditional UI. Subtypes match their base type.
+ *
+ * @param {string} serviceType - The service type to look for, e.g. `"_Volumio._tcp.local."`.
+ * @returns {Promise<boolean>} A promise that resolves with `true` if a device exposes the service type.
+ * @example
+ * if (await scanner.hasDeviceOfServiceType("_Volumio._tcp.local.")) showVolumioSetup();
+ */
+async function hasDeviceOfServiceType(serviceType) {
+	return await invoke("plugin:lan-scanner|has_device_of_service_type", { serviceType });
+}
+
+/**
+ * Retrieves the number of devices with at least one service of each device type, the same
+ * counts `onTypeCounts` delivers. Types are keyed by their `kind`, or their label for a custom type.
+ *
+ * @returns {Promise<Record<string, number>>} A promise that resolves with the count per device type.
+ * @example
+ * const counts = await scanner.getTypeCounts();
+ * console.log(`${counts.bluesound ?? 0} Bluesound so far`);
+ */
+async function getTypeCounts() {
+	return await invoke("plugin:lan-scanner|get_type_counts");
+}
+
+/**
+ * Retrieves the discovered devices matching every criterion of the filter in a single round-trip.
+ *
+ * @param {DeviceFilter} filter - The criteria to match.
+ * @returns {Promise<Device[]>} A promise that resolves with the matching devices. Rejects if `subnet` is malformed.
+ * @example
+ * const kitchen = await scanner.queryDevices({
+ *   types: [{ kind: "bluesound" }, { kind: "volumio" }],
+ *   nameContains: "kitchen",
+ *   subnet: "192.168.1.0/24",
+ * });
+ */
+async function queryDevices(filter) {
+	return await invoke("plugin:lan-scanner|query_devices", { filter });
+}
+
+/**
+ * Retrieves the discovered devices that expose one service type but not another, e.g. for capability-gap analysis.
+ *
+ * @param {string} has - The service type the devices must expose.
+ * @param {string} lacks - The service type the devices must not expose.
+ * @returns {Promise<Device[]>} A promise that resolves with the matching devices.
+ * @example
+ * const noSpotify = await scanner.devicesWithServiceNotOther("_http._tcp.local.", "_spotify-connect._tcp.local.");
+ */
+async function devicesWithServiceNotOther(has, lacks) {
+	return await invoke("plugin:lan-scanner|devices_with_service_not_other", { has, lacks });
+}
+
+/**
+ * Retrieves the discovered devices exposing at least `minServices` services, those with the most services first.
+ * Handy for surfacing full-featured devices above single-purpose ones.
+ *
+ * @param {number} minServices - The minimum number of services a device must expose.
+ * @returns {Promise<Device[]>} A promise that resolves with the matching devices, sorted by service count descending.
+ * @example
+ * const capable = await scanner.getMultiserviceDevices(2);
+ */
+async function getMultiserviceDevices(minServices) {
+	return await invoke("plugin:lan-scanner|get_multiservice_devices", { minServices });
+}
+
+/**
+ * Retrieves the names shared by devices at different IPs, e.g. two misconfigured devices both
+ * advertised as "Kitchen", so the user can be asked to rename one.
+ *
+ * @returns {Promise<[string, string[]][]>} A promise that resolves with `[name, ips]` pairs, sorted by name.
+ * @example
+ * for (const [name, ips] of await scanner.getNameConflicts()) {
+ *   console.warn(`"${name}" is used by ${ips.join(", ")}`);
+ * }
+ */
+async function getNameConflicts() {
+	return await invoke("plugin:lan-scanner|get_name_conflicts");
+}
+
+/**
+ * Retrieves the device of the given type that responded quickest, i.e. with the lowest `discoveryTimeMs`.
+ * Useful for auto-selecting a default renderer.
+ *
+ * @param {DeviceType} deviceType - The device type to match against each device's services.
+ * @returns {Promise<Device | null>} A promise that resolves with the fastest matching device, or `null` if none was found.
+ * @example
+ * const renderer = await scanner.getFastestDeviceOfType({ kind: "bluesound" });
+ * if (renderer) console.log(`Defaulting to ${renderer.name}`);
+ */
+async function getFastestDeviceOfType(deviceType) {
+	return await invoke("plugin:lan-scanner|get_fastest_device_of_type", { deviceType });
+}
+
+/**
+ * Retrieves the discovered device with the given fingerprint.
+ * Fingerprints survive IP changes and app restarts, so they make a good key for remembering devices.
+ *
+ * @param {string} fingerprint - The `fingerprint` of a previously seen device.
+ * @returns {Promise<Device | null>} A promise that resolves with the device, or `null` if it wasn't discovered.
+ * @example
+ * const lastUsed = await scanner.getDeviceByFingerprint(localStorage.getItem("renderer"));
+ */
+async function getDeviceByFingerprint(fingerprint) {
+	return await invoke("plugin:lan-scanner|get_device_by_fingerprint", { fingerprint });
+}
+
+/**
+ * Attaches a user tag (e.g. "bedroom" or "favorite") to a discovered device.
+ * Tags stay on the device as its services are refreshed. Emits `device-updated` when the tag is new.
+ *
+ * @param {string} ip - The IP address of the device.
+ * @param {string} tag - The tag to attach.
+ * @returns {Promise<Device>} A promise that resolves with the updated device. Rejects if no device has that IP.
+ * @example
+ * await scanner.addDeviceTag("192.168.1.20", "favorite");
+ */
+async function addDeviceTag(ip, tag) {
+	return await invoke("plugin:lan-scanner|add_device_tag", { ip, tag });
+}
+
+/**
+ * Removes a user tag from a discovered device. Emits `device-updated` when the tag was present.
+ *
+ * @param {string} ip - The IP address of the device.
+ * @param {string} tag - The tag to remove.
+ * @returns {Promise<Device>} A promise that resolves with the updated device. Rejects if no device has that IP.
+ * @example
+ * await scanner.removeDeviceTag("192.168.1.20", "favorite");
+ */
+async function removeDeviceTag(ip, tag) {
+	return await invoke("plugin:lan-scanner|remove_device_tag", { ip, tag });
+}
+
+/**
+ * Forces resolution of a service instance that was found but never resolved on its own.
+ * Re-queries the service type on the running scan and waits up to 5 seconds for the instance.
+ *
+ * @param {string} fullname - The full instance name, e.g. `Living Room._spotify-connect._tcp.local.`.
+ * @param {string} serviceType - The instance's service type, e.g. `_spotify-connect._tcp.local.`.
+ * @returns {Promise<Device | null>} A promise that resolves with the device the instance belongs to, or `null` on timeout. Rejects if no scan is running.
+ * @example
+ * const device = await scanner.resolveInstance("Living Room._spotify-connect._tcp.local.", "_spotify-connect._tcp.local.");
+ */
+async function resolveInstance(fullname, serviceType) {
+	return await invoke("plugin:lan-scanner|resolve_instance", { fullname, serviceType });
+}
+
+/**
+ * Resolves a single service instance by its full name, e.g. for a deep link to a known device,
+ * without browsing every service type. Works with or without a running scan; without one, a
+ * temporary mDNS daemon is started and shut down again, and the device isn't added to the device list.
+ *
+ * @param {string} fullname - The full instance name, e.g. `Living Room._spotify-connect._tcp.local.`.
+ * @param {number} timeoutMs - How long to wait for the instance to resolve, in milliseconds.
+ * @returns {Promise<Device | null>} A promise that resolves with the instance's device, or `null` on timeout. Rejects if `fullname` has no service type.
+ * @example
+ * const device = await scanner.resolveFullname("Living Room._spotify-connect._tcp.local.", 3000);
+ */
+async function resolveFullname(fullname, timeoutMs) {
+	return await invoke("plugin:lan-scanner|resolve_fullname", { fullname, timeoutMs });
+}
+
+/**
+ * Lists the service types advertised on the network, by browsing the DNS-SD meta-query
+ * `_services._dns-sd._udp.local.` on a temporary mDNS daemon, independent of any running scan.
+ *
+ * @param {number} durationSecs - How long to listen for advertised types, in seconds.
+ * @returns {Promise<string[]>} A promise that resolves with the service types found, sorted, e.g. `["_googlecast._tcp.local.", "_http._tcp.local."]`.
+ * @example
+ * const serviceTypes = await scanner.discoverServiceTypes(3);
+ * serviceTypes.forEach(serviceType => console.log(`Advertised: ${serviceType}`));
+ */
+async function discoverServiceTypes(durationSecs) {
+	return await invoke("plugin:lan-scanner|discover_service_types", { durationSecs });
+}
+
+/**
+ * Retrieves a rough estimate of the memory held by the device cache and the running scan's
+ * deduplication set. It sums allocated collection and string capacities, so treat it as a trend
+ * indicator rather than an exact figure.
+ *
+ * @returns {Promise<number>} A promise that resolves with the estimated size in bytes.
+ * @example
+ * const bytes = await scanner.getMemoryEstimate();
+ * if (bytes > 512 * 1024) await scanner.stopScan();
+ */
+async function getMemoryEstimate() {
+	return await invoke("plugin:lan-scanner|get_memory_estimate");
+}
+
+/**
+ * Streams a single device's updates, for views like a device detail page.
+ * The callback receives the device immediately if it is already known, then again on every update
+ * to it until the scan stops.
+ *
+ * @param {string} ip - The IP address of the device to watch.
+ * @param {(device: Device) => void} callback - The function to call with each version of the device.
+ * @returns {Promise<void>} A promise that resolves once the watcher is registered.
+ * @example
+ * await scanner.watchDevice("192.168.1.20", (device) => renderDetails(device));
+ */
+async function watchDevice(ip, callback) {
+	const channel = new Channel();
+	channel.onmessage = callback;
+	await invoke("plugin:lan-scanner|watch_device", { ip, channel });
+}
+
+/**
+ * Re-runs device classification over every cached service, so updated classification rules
+ * apply without rescanning. Emits `device-updated` for each device whose types changed.
+ *
+ * @returns {Promise<Device[]>} A promise that resolves with the devices whose types changed.
+ * @example
+ * const changed = await scanner.reclassifyAll();
+ * console.log(`${changed.length} devices reclassified`);
+ */
+async function reclassifyAll() {
+	return await invoke("plugin:lan-scanner|reclassify_all");
+}
+
+/**
+ * Prunes the online devices that have not been seen for `ttlMs` milliseconds right away,
+ * emitting `device-removed` for each, or marking them offline and emitting `device-updated`
+ * when `keepOfflineDevices` is set. `type-counts` is emitted afterwards.
+ *
+ * @param {number} [ttlMs] - How long a device may go unseen before it is pruned. Defaults to 2 minutes.
+ * @returns {Promise<number>} A promise that resolves with the number of devices pruned.
+ * @example
+ * const pruned = await scanner.pruneStaleDevices(60_000);
+ * console.log(`${pruned} offline devices cleaned up`);
+ */
+async function pruneStaleDevices(ttlMs) {
+	return await invoke("plugin:lan-scanner|prune_stale_devices", { ttlMs });
+}
+
+/**
+ * Retrieves the discovered devices as a compact binary buffer, skipping JSON serialization.
+ * This is an opt-in fast path for memory-constrained webviews; decode it with `decodePackedDevices`.
+ *
+ * The layout is little-endian. Strings are a `u16` byte length followed by UTF-8 bytes:
+ * `u32 deviceCount`, then per device `string name, string ip, u64 discoveryTimeMs, u16 serviceCount`,
+ * then per service `string serviceType, u16 port, u8 deviceType, u64 lastSeenMs`.
+ * Device type tags: 0 `bluesound`, 1 `volumio`, 2 `spotifyConnect`, 3 `qobuzConnect`, 4 `generic`,
+ * 5 for a custom label, which follows the tag as a `string`, 6 `roon`, and 7 `chromecast`.
+ *
+ * @returns {Promise<ArrayBuffer>} A promise that resolves with the packed devices.
+ * @example
+ * const devices = decodePackedDevices(await scanner.getDevicesPacked());
+ */
+async function getDevicesPacked() {
+	return await invoke("plugin:lan-scanner|get_devices_packed");
+}
+
+/** The `kind` of each built-in device type, indexed by its packed tag. Tag 5 is a custom label. */
+const PACKED_DEVICE_TYPES = [
+	"bluesound",
+	"volumio",
+	"spotifyConnect",
+	"qobuzConnect",
+	"generic",
+	null,
+	"roon",
+	"chromecast",
+];
+
+/** The packed tag of a device type carrying a custom label. */
+const PACKED_CUSTOM_DEVICE_TYPE = 5;
+
+/**
+ * Decodes the buffer returned by `getDevicesPacked`.
+ *
+ * @param {ArrayBuffer} buffer - The packed devices.
+ * @returns {PackedDevice[]} The decoded devices.
+ * @example
+ * const devices = scanner.decodePackedDevices(await scanner.getDevicesPacked());
+ * devices.forEach(device => console.log(`Found: ${device.name} at ${device.ip}`));
+ */
+function decodePackedDevices(buffer) {
+	const view = new DataView(buffer);
+	const text = new TextDecoder();
+	let offset = 0;
+	const u8 = () => view.getUint8(offset++);
+	const u16 = () => ((offset += 2), view.getUint16(offset - 2, true));
+	const u32 = () => ((offset += 4), view.getUint32(offset - 4, true));
+	const u64 = () => ((offset += 8), Number(view.getBigUint64(offset - 8, true)));
+	const str = () => {
+		const len = u16();
+		offset += len;
+		return text.decode(new Uint8Array(buffer, offset - len, len));
+	};
+	const deviceType = () => {
+		const tag = u8();
+		if (tag === PACKED_CUSTOM_DEVICE_TYPE) return { kind: "custom", value: str() };
+		return { kind: PACKED_DEVICE_TYPES[tag] ?? "generic" };
+	};
+
+	/** @type {PackedDevice[]} */
+	const devices = [];
+	for (let i = u32(); i > 0; i--) {
+		const name = str();
+		const ip = str();
+		const discoveryTimeMs = u64();
+		const services = [];
+		for (let j = u16(); j > 0; j--) {
+			services.push({
+				serviceType: str(),
+				port: u16(),
+				deviceType: deviceType(),
+				lastSeenMs: u64(),
+			});
+		}
+		devices.push({ name, ip, discoveryTimeMs, services });
+	}
+	return devices;
+}
+
+/**
+ * Temporarily makes the running scan more aggressive.
+ * For the given number of seconds every browsed service type is re-queried once per second,
+ * then the scan returns to its normal pace. Calling it again restarts the boost window.
+ *
+ * @param {number} durationSecs - How long the boost lasts, in seconds.
+ * @returns {Promise<void>} A promise that resolves once the boost has started. Rejects if no scan is running.
+ * @example
+ * // "Find faster" button
+ * await scanner.boostScan(5);
+ */
+async function boostScan(durationSecs) {
+	await invoke("plugin:lan-scanner|boost_scan", { durationSecs });
+}
+
+/**
+ * Refreshes a single device's services by re-browsing its service types on the running scan,
+ * e.g. when its detail page opens. The refreshed device arrives through `onDeviceUpdated` if
+ * anything changed, and through `onDeviceSeen` otherwise.
+ *
+ * @param {string} ip - The `ip` of the device to refresh.
+ * @returns {Promise<void>} A promise that resolves once the service types are being re-browsed. Rejects if no scan is running or the device is unknown.
+ * @example
+ * await scanner.rescanDevice(device.ip);
+ */
+async function rescanDevice(ip) {
+	await invoke("plugin:lan-scanner|rescan_device", { ip });
+}
+
+/**
+ * Advertises a service of this host on the running scan's mDNS daemon, so companion devices can
+ * discover the app. The service is withdrawn when the scan stops, and while it is paused.
+ * Requires the opt-in `lan-scanner:allow-register-service` permission.
+ *
+ * @param {string} serviceType - The service type, e.g. `"_myapp._tcp.local."`.
+ * @param {string} instanceName - The instance name companion devices see, e.g. `"Living Room Remote"`.
+ * @param {number} port - The port the app listens on.
+ * @param {Record<string, string>} [txt] - The TXT record properties to advertise.
+ * @returns {Promise<string>} A promise that resolves with the service's fullname, to pass to `unregisterService`. Rejects if no scan is running.
+ * @example
+ * const fullname = await scanner.registerService("_myapp._tcp.local.", "Desk", 8080, { version: "1.2" });
+ */
+async function registerService(serviceType, instanceName, port, txt = {}) {
+	return await invoke("plugin:lan-scanner|register_service", { serviceType, instanceName, port, txt });
+}
+
+/**
+ * Withdraws a service advertised with `registerService`.
+ * Requires the opt-in `lan-scanner:allow-unregister-service` permission.
+ *
+ * @param {string} fullname - The fullname `registerService` resolved with.
+ * @returns {Promise<void>} A promise that resolves once the service is withdrawn. Rejects if no such service is registered.
+ * @example
+ * await scanner.unregisterService(fullname);
+ */
+async function unregisterService(fullname) {
+	await invoke("plugin:lan-scanner|unregister_service", { fullname });
+}
+
+/**
+ * Listens for the device list being cleared at the start of a scan or by `clearDevices`.
+ * Fires before any `new-device` event of the scan, so the UI can drop stale devices and show a spinner.
+ *
+ * @param {(seq: number, scanId: string | null) => void} callback - The function to call when the list is cleared, with the event's sequence number and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDevicesCleared(() => {
+ *   deviceList.replaceChildren();
+ *   spinner.hidden = false;
+ * });
+ */
+async function onDevicesCleared(callback) {
+	return await listen(eventName("devices-cleared"), (event) => {
+		callback(event.payload.seq, event.payload.scanId);
+	});
 }
 
 /**
  * Listens for new devices discovered on the network.
- * The callback will be invoked each time a new device is found.
+ * The callback will be invoked once per device, when it is first added to the device list, so it
+ * can be appended as is. Later changes to the device fire `device-updated`.
  *
- * @param {(device: Device) => void} callback - The function to call with the new device information.
+ * Every event carries a sequence number that increases monotonically within a scan and resets
+ * when a new scan starts. Compare it across events to reorder or discard out-of-order deliveries.
+ *
+ * @param {(device: Device, seq: number, scanId: string | null) => void} callback - The function to call with the new device information, the event's sequence number, and the id of its scan.
  * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
  * @example
  * const unlisten = await scanner.onNewDevice((device) => {
@@ -107,16 +1073,202 @@ async function getDiscoveredDevices() {
  * // unlisten();
  */
 async function onNewDevice(callback) {
-	return await listen("new-device", (event) => {
-		callback(event.payload);
+	return await listen(eventName("new-device"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a known device's service resolving again without changing anything, which fires
+ * instead of `device-updated` to keep the event volume down on busy networks.
+ *
+ * @param {(seen: { ip: string, lastSeenMs: number }, seq: number, scanId: string | null) => void} callback - The function to call with the device's IP and when it was seen, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDeviceSeen(({ ip }) => markFresh(ip));
+ */
+async function onDeviceSeen(callback) {
+	return await listen(eventName("device-seen"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for the number of devices of each device type changing, whenever a device gains or
+ * loses a service during a scan. Each device counts once per type, however many services of that
+ * type it has. Types are keyed as by `getTypeCounts`.
+ *
+ * @param {(counts: Record<string, number>, seq: number, scanId: string | null) => void} callback - The function to call with the count per device type, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onTypeCounts((counts) => renderStatsStrip(counts));
+ */
+async function onTypeCounts(callback) {
+	return await listen(eventName("type-counts"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for changes to an already discovered device, such as a new service, a service's port
+ * or type changing, its tags, or the device coming back online. Carries the full device, so it
+ * can replace the listed one by `ip`.
+ *
+ * @param {(device: Device, seq: number, scanId: string | null) => void} callback - The function to call with the updated device, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDeviceUpdated((device) => {
+ *   console.log(`${device.name} is now tagged ${device.tags.join(", ")}`);
+ * });
+ */
+async function onDeviceUpdated(callback) {
+	return await listen(eventName("device-updated"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a known device moving to a new IP address, e.g. after a DHCP lease renewal.
+ * Only fires when the scan was started with `trackIpChanges`. The device is then re-keyed under its new IP.
+ *
+ * @param {(change: { oldIp: string, newIp: string }, seq: number, scanId: string | null) => void} callback - The function to call with the old and new IPs, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDeviceIpChanged(({ oldIp, newIp }) => {
+ *   console.log(`Device moved from ${oldIp} to ${newIp}`);
+ * });
+ */
+async function onDeviceIpChanged(callback) {
+	return await listen(eventName("device-ip-changed"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for devices being removed from the device list, either because their last service went
+ * away mid-scan or because `pruneStaleDevices` removed them.
+ *
+ * @param {(removal: { ip: string, serviceType: string | null }, seq: number, scanId: string | null) => void} callback - The function to call with the device's IP, the service type whose removal took its last service (`null` when pruned), the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDeviceRemoved(({ ip }) => {
+ *   devices.delete(ip);
+ * });
+ */
+async function onDeviceRemoved(callback) {
+	return await listen(eventName("device-removed"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a scan starting. Fires once the scan is actually running, so it never fires when
+ * `startScan` fails, e.g. because the mDNS daemon could not be created.
+ *
+ * @param {(scan: ScanStarted, seq: number, scanId: string | null) => void} callback - The function to call with the scan's duration and service types, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanStarted(({ durationSecs }) => {
+ *   setScanning(true);
+ *   startCountdown(durationSecs);
+ * });
+ */
+async function onScanStarted(callback) {
+	return await listen(eventName("scan-started"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for the mDNS daemon failing to start, after `startScan` has retried. Fires right
+ * before `startScan` rejects, with the likely cause so the UI can suggest a fix.
+ *
+ * @param {(error: DaemonError, seq: number, scanId: string | null) => void} callback - The function to call with the error, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDaemonError(({ cause }) => {
+ *   if (cause === "permissionDenied") showFirewallHint();
+ * });
+ */
+async function onDaemonError(callback) {
+	return await listen(eventName("daemon-error"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a scan failing to browse for one of its service types. The scan carries on with
+ * the others, so `anySucceeded` tells a partial failure from a total one.
+ *
+ * @param {(error: BrowseError, seq: number, scanId: string | null) => void} callback - The function to call with the error, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onBrowseError(({ serviceType, anySucceeded }) => {
+ *   if (anySucceeded) showWarning(`Couldn't browse for ${serviceType}`);
+ *   else showScanFailed();
+ * });
+ */
+async function onBrowseError(callback) {
+	return await listen(eventName("browse-error"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a scan started with `maxDevices` reaching its cap. Fires once per scan; new
+ * devices are ignored from then on, while known ones keep updating.
+ *
+ * @param {(maxDevices: number, seq: number, scanId: string | null) => void} callback - The function to call with the cap, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDeviceCapReached((maxDevices) => {
+ *   showWarning(`Showing the first ${maxDevices} devices only`);
+ * });
+ */
+async function onDeviceCapReached(callback) {
+	return await listen(eventName("device-cap-reached"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for the periodic heartbeat of a continuous scan started with `heartbeatIntervalSecs`,
+ * carrying the current device and service counts even when nothing changed.
+ *
+ * @param {(heartbeat: { deviceCount: number, serviceCount: number }, seq: number, scanId: string | null) => void} callback - The function to call with the counts, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onDeviceHeartbeat(({ deviceCount }) => {
+ *   markScannerAlive(deviceCount);
+ * });
+ */
+async function onDeviceHeartbeat(callback) {
+	return await listen(eventName("device-heartbeat"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for the summary of a scan that ran its full duration, delivered right before `scan-stopped`. It doesn't fire for a scan stopped any other way, e.g. by `stopScan`.
+ *
+ * @param {(report: ScanReport, seq: number, scanId: string | null) => void} callback - The function to call with the report, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanComplete((report) => {
+ *   showSummary(`Found ${report.deviceCount} devices across ${report.serviceCount} services`);
+ * });
+ */
+async function onScanComplete(callback) {
+	return await listen(eventName("scan-complete"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
 	});
 }
 
 /**
  * Listens for the scan to stop.
- * The callback is invoked when the scan is stopped, either manually or by the 30-second timeout.
+ * The callback is invoked when the scan is stopped, either manually or once its duration elapses.
  *
- * @param {() => void} callback - The function to call when the scan stops.
+ * @param {(reason: ScanEndReason, seq: number, scanId: string | null) => void} callback - The function to call when the scan stops, with why it ended, the event's sequence number, and the id of its scan.
  * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
  * @example
  * const unlisten = await scanner.onScanStopped(() => {
@@ -127,8 +1279,36 @@ async function onNewDevice(callback) {
  * // unlisten();
  */
 async function onScanStopped(callback) {
-	return await listen("scan-stopped", () => {
-		callback();
+	return await listen(eventName("scan-stopped"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for the scan being paused by `pauseScan`.
+ *
+ * @param {(seq: number, scanId: string | null) => void} callback - The function to call when the scan is paused, with the event's sequence number and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanPaused(() => setPaused(true));
+ */
+async function onScanPaused(callback) {
+	return await listen(eventName("scan-paused"), (event) => {
+		callback(event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a paused scan being resumed by `resumeScan`.
+ *
+ * @param {(seq: number, scanId: string | null) => void} callback - The function to call when the scan is resumed, with the event's sequence number and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanResumed(() => setPaused(false));
+ */
+async function onScanResumed(callback) {
+	return await listen(eventName("scan-resumed"), (event) => {
+		callback(event.payload.seq, event.payload.scanId);
 	});
 }
 
@@ -136,7 +1316,7 @@ async function onScanStopped(callback) {
  * Listens for the scan countdown tick.
  * The callback is invoked every second with the remaining time before the scan automatically stops.
  *
- * @param {(seconds: number) => void} callback - The function to call with the remaining seconds.
+ * @param {(seconds: number, seq: number, scanId: string | null) => void} callback - The function to call with the remaining seconds, the event's sequence number, and the id of its scan.
  * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
  * @example
  * const unlisten = await scanner.onScanTick((seconds) => {
@@ -147,8 +1327,57 @@ async function onScanStopped(callback) {
  * // unlisten();
  */
 async function onScanTick(callback) {
-	return await listen("scan-tick", (event) => {
-		callback(event.payload);
+	return await listen(eventName("scan-tick"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for the scan's progress, reported once per second as the fraction of its duration elapsed.
+ * The last event reports exactly `1`. Never fires for continuous scans.
+ *
+ * @param {(progress: number, seq: number, scanId: string | null) => void} callback - The function to call with the progress between `0` and `1`, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanProgress((progress) => {
+ *   progressBar.value = progress;
+ * });
+ */
+async function onScanProgress(callback) {
+	return await listen(eventName("scan-progress"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a scan boost starting.
+ *
+ * @param {(durationSecs: number, seq: number, scanId: string | null) => void} callback - The function to call with the boost duration, the event's sequence number, and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanBoosted((seconds) => {
+ *   console.log(`Searching harder for ${seconds}s`);
+ * });
+ */
+async function onScanBoosted(callback) {
+	return await listen(eventName("scan-boosted"), (event) => {
+		callback(event.payload.payload, event.payload.seq, event.payload.scanId);
+	});
+}
+
+/**
+ * Listens for a scan boost ending, after which the scan continues at its normal pace.
+ *
+ * @param {(seq: number, scanId: string | null) => void} callback - The function to call when the boost ends, with the event's sequence number and the id of its scan.
+ * @returns {Promise<UnlistenFn>} A promise that resolves with a function to unregister the listener.
+ * @example
+ * const unlisten = await scanner.onScanBoostEnded(() => {
+ *   console.log('Back to normal scanning');
+ * });
+ */
+async function onScanBoostEnded(callback) {
+	return await listen(eventName("scan-boost-ended"), (event) => {
+		callback(event.payload.seq, event.payload.scanId);
 	});
 }
 
@@ -156,13 +1385,75 @@ async function onScanTick(callback) {
  * @type {LanScannerPlugin}
  */
 const __TAURI_PLUGIN_LAN_SCANNER_API__ = {
+	setEventPrefix,
 	startScan,
+	startScanWithChannel,
 	stopScan,
+	forceShutdown,
 	isScanning,
+	pauseScan,
+	resumeScan,
+	isPaused,
+	getLastScanReason,
+	getLastScanReport,
+	getScanStats,
+	getDiscoveryLatencyStats,
+	scanUntilQuiet,
+	scanOnce,
 	getDiscoveredDevices,
+	getDevicesWithFreshness,
+	clearDevices,
+	replayDevices,
+	getDeviceByIp,
+	getRawRecords,
+	getDevicesByPort,
+	getDevicesByType,
+	hasDeviceOfServiceType,
+	getTypeCounts,
+	queryDevices,
+	devicesWithServiceNotOther,
+	getMultiserviceDevices,
+	getNameConflicts,
+	getDefaultConfig,
+	getCurrentScanConfig,
+	getScanTimeRemaining,
+	getDevicesPacked,
+	decodePackedDevices,
+	getFastestDeviceOfType,
+	getDeviceByFingerprint,
+	addDeviceTag,
+	removeDeviceTag,
+	resolveInstance,
+	resolveFullname,
+	discoverServiceTypes,
+	getMemoryEstimate,
+	watchDevice,
+	reclassifyAll,
+	pruneStaleDevices,
+	boostScan,
+	rescanDevice,
+	registerService,
+	unregisterService,
+	onDevicesCleared,
 	onNewDevice,
+	onDeviceSeen,
+	onTypeCounts,
+	onDeviceUpdated,
+	onDeviceIpChanged,
+	onDeviceRemoved,
+	onScanStarted,
+	onDaemonError,
+	onBrowseError,
+	onDeviceCapReached,
+	onDeviceHeartbeat,
+	onScanComplete,
 	onScanStopped,
+	onScanPaused,
+	onScanResumed,
 	onScanTick,
+	onScanProgress,
+	onScanBoosted,
+	onScanBoostEnded,
 };
 
 globalThis.__TAURI__.lanScanner = __TAURI_PLUGIN_LAN_SCANNER_API__;
\ No newline at end of file
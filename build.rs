@@ -1,5 +1,53 @@
 fn main() {
-    tauri_plugin::Builder::new(&["start_scan", "stop_scan", "is_scanning", "get_discovered_devices"])
-        .global_api_script_path("./api.js")
-        .build();
+    tauri_plugin::Builder::new(&[
+        "start_scan",
+        "stop_scan",
+        "is_scanning",
+        "get_discovered_devices",
+        "get_devices_by_port",
+        "boost_scan",
+        "get_default_config",
+        "get_devices_packed",
+        "get_fastest_device_of_type",
+        "scan_until_quiet",
+        "add_device_tag",
+        "remove_device_tag",
+        "resolve_instance",
+        "get_memory_estimate",
+        "get_last_scan_reason",
+        "watch_device",
+        "reclassify_all",
+        "query_devices",
+        "get_device_by_fingerprint",
+        "devices_with_service_not_other",
+        "get_multiservice_devices",
+        "get_last_scan_report",
+        "prune_stale_devices",
+        "get_device_by_ip",
+        "rescan_device",
+        "scan_once",
+        "clear_devices",
+        "get_devices_by_type",
+        "get_scan_stats",
+        "pause_scan",
+        "resume_scan",
+        "is_paused",
+        "get_current_scan_config",
+        "register_service",
+        "unregister_service",
+        "get_name_conflicts",
+        "replay_devices",
+        "get_type_counts",
+        "get_discovery_latency_stats",
+        "has_device_of_service_type",
+        "get_scan_time_remaining",
+        "resolve_fullname",
+        "start_scan_with_channel",
+        "get_raw_records",
+        "force_shutdown",
+        "get_devices_with_freshness",
+        "discover_service_types",
+    ])
+    .global_api_script_path("./api.js")
+    .build();
 }
@@ -0,0 +1,115 @@
+//! Looks up MAC addresses in the operating system's ARP table.
+//!
+//! Linux exposes the table in `/proc/net/arp`. Elsewhere the output of `arp -a` is parsed, which
+//! macOS, the BSDs and Windows all print in slightly different layouts, so lines are matched by
+//! their IP and MAC tokens rather than by column.
+
+use std::net::Ipv4Addr;
+
+/// Returns the MAC address the ARP table holds for `ip`, as lowercase colon-separated octets, or
+/// `None` if the table can't be read or has no complete entry for it.
+pub async fn lookup_mac(ip: Ipv4Addr) -> Option<String> {
+    let table = read_arp_table().await?;
+    find_mac(&table, ip)
+}
+
+#[cfg(target_os = "linux")]
+async fn read_arp_table() -> Option<String> {
+    tokio::fs::read_to_string("/proc/net/arp")
+        .await
+        .map_err(|e| log::debug!("Failed to read /proc/net/arp: {}", e))
+        .ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn read_arp_table() -> Option<String> {
+    let mut command = tokio::process::Command::new("arp");
+    command.arg("-a");
+    #[cfg(windows)]
+    {
+        // CREATE_NO_WINDOW, so a GUI app doesn't flash a console.
+        command.creation_flags(0x0800_0000);
+    }
+    let output = command
+        .output()
+        .await
+        .map_err(|e| log::debug!("Failed to run arp -a: {}", e))
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Finds the MAC address on the line of an ARP table listing `ip`.
+pub fn find_mac(table: &str, ip: Ipv4Addr) -> Option<String> {
+    table.lines().find_map(|line| {
+        let mut tokens = line
+            .split_whitespace()
+            .map(|token| token.trim_matches(|c| c == '(' || c == ')'));
+        if !tokens
+            .clone()
+            .any(|token| token.parse::<Ipv4Addr>() == Ok(ip))
+        {
+            return None;
+        }
+        tokens.find_map(parse_mac)
+    })
+}
+
+/// Parses a MAC address separated by colons or dashes. macOS drops leading zeros, so octets may
+/// be a single digit. The all-zero address Linux lists for incomplete entries yields `None`.
+fn parse_mac(token: &str) -> Option<String> {
+    let octets = token
+        .split(|c| c == ':' || c == '-')
+        .map(|octet| {
+            if octet.is_empty() || octet.len() > 2 || !octet.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return None;
+            }
+            u8::from_str_radix(octet, 16).ok()
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    if octets.len() != 6 || octets.iter().all(|&octet| octet == 0) {
+        return None;
+    }
+    Some(
+        octets
+            .iter()
+            .map(|octet| format!("{octet:02x}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_mac;
+    use std::net::Ipv4Addr;
+
+    const IP: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 20);
+
+    #[test]
+    fn finds_mac_in_proc_net_arp() {
+        let table = "IP address       HW type     Flags       HW address            Mask     Device\n\
+                     192.168.1.2      0x1         0x2         11:22:33:44:55:66     *        eth0\n\
+                     192.168.1.20     0x1         0x2         AA:BB:CC:DD:EE:FF     *        eth0\n\
+                     192.168.1.30     0x1         0x0         00:00:00:00:00:00     *        eth0\n";
+        assert_eq!(find_mac(table, IP).as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+        assert_eq!(find_mac(table, Ipv4Addr::new(192, 168, 1, 30)), None);
+        assert_eq!(find_mac(table, Ipv4Addr::new(192, 168, 1, 40)), None);
+    }
+
+    #[test]
+    fn finds_mac_in_arp_a_output() {
+        let macos = "? (192.168.1.2) at (incomplete) on en0 ifscope [ethernet]\n\
+                     ? (192.168.1.20) at a:b:c:d:e:f on en0 ifscope [ethernet]\n";
+        assert_eq!(find_mac(macos, IP).as_deref(), Some("0a:0b:0c:0d:0e:0f"));
+        assert_eq!(find_mac(macos, Ipv4Addr::new(192, 168, 1, 2)), None);
+
+        let windows = "Interface: 192.168.1.5 --- 0xb\n  \
+                       Internet Address      Physical Address      Type\n  \
+                       192.168.1.20          aa-bb-cc-dd-ee-ff     dynamic\n";
+        assert_eq!(find_mac(windows, IP).as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+    }
+}
@@ -1,17 +1,77 @@
-use super::models::{Device, DeviceType};
-use mdns_sd::{ServiceDaemon, ServiceEvent};
-use std::collections::{HashMap, HashSet};
-use std::net::IpAddr;
+use super::arp;
+use super::error::ScanError;
+use super::models::{
+    BrowseError, CurrentScanConfig, DaemonError, DaemonErrorCause, DefaultScanConfig, Device,
+    DeviceFilter, DeviceFreshness, DeviceHeartbeat, DeviceIpChange, DeviceRemoved, DeviceSeen,
+    DeviceSort, DeviceType, DiscoveryLatencyStats, IpVersion, RawServiceRecord, ScanConfig,
+    ScanEndReason, ScanReport, ScanStarted, ScanStats, ScanStreamMessage, SequencedEvent,
+    TimeoutAction,
+};
+use super::oui;
+use super::packed::pack_devices;
+use mdns_sd::{IfKind, ScopedIp, ServiceDaemon, ServiceEvent, ServiceInfo, TxtProperties};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, Response};
 use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
-use tokio::sync::Mutex;
+use tokio::net::TcpStream;
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, Mutex};
 use tokio::task::JoinHandle;
 
 const BLUESOUND_SERVICE_TYPE: &str = "_musc._tcp.local.";
 const VOLUMIO_SERVICE_TYPE: &str = "_http._tcp.local.";
 const SPOTIFY_CONNECT_SERVICE_TYPE: &str = "_spotify-connect._tcp.local.";
 const QOBUZ_CONNECT_SERVICE_TYPE: &str = "_qobuz-connect._tcp.local.";
+const ROON_SERVICE_TYPE: &str = "_raat._tcp.local.";
+const CHROMECAST_SERVICE_TYPE: &str = "_googlecast._tcp.local.";
+/// The DNS-SD meta-query answered with every service type advertised on the network.
+const SERVICE_TYPE_ENUMERATION: &str = "_services._dns-sd._udp.local.";
+
+// DLNA renderers have no built-in type: they announce themselves over SSDP
+// (`urn:schemas-upnp-org:device:MediaRenderer`), not mDNS, so no service type finds them.
+
+/// The service types every scan browses unless its configuration replaces them.
+const BUILTIN_SERVICE_TYPES: [&str; 6] = [
+    BLUESOUND_SERVICE_TYPE,
+    VOLUMIO_SERVICE_TYPE,
+    SPOTIFY_CONNECT_SERVICE_TYPE,
+    QOBUZ_CONNECT_SERVICE_TYPE,
+    ROON_SERVICE_TYPE,
+    CHROMECAST_SERVICE_TYPE,
+];
+
+/// How long a scan runs before it stops automatically, unless `start_scan` is given a duration.
+const DEFAULT_SCAN_DURATION_SECS: u64 = 30;
+/// The shortest duration a scan runs for, unless the builder sets another floor.
+const DEFAULT_MIN_SCAN_DURATION_SECS: u64 = 3;
+
+/// The longest reliable mode waits between re-browses.
+const MAX_REBROWSE_INTERVAL: Duration = Duration::from_secs(60);
+/// The shortest interval `query_interval_ms` may set, keeping the multicast traffic reasonable;
+/// RFC 6762 asks for at least a second between the first two queries.
+const MIN_QUERY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long `resolve_instance` waits for an instance to resolve.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a device may go unseen before `prune_stale_devices` removes it, unless the caller
+/// passes its own TTL.
+const DEFAULT_STALE_TTL: Duration = Duration::from_secs(120);
+/// How many times `start_scan` tries to create the mDNS daemon before giving up.
+const DAEMON_INIT_ATTEMPTS: u32 = 3;
+/// How long `start_scan` waits between attempts to create the mDNS daemon.
+const DAEMON_INIT_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// How long a reachability check waits for the connection, unless the scan configures it.
+const DEFAULT_REACHABILITY_TIMEOUT: Duration = Duration::from_millis(500);
+/// The shortest time between two events for the same device, unless the scan configures it.
+const DEFAULT_DEVICE_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+/// How many devices a `subscribe_devices` channel buffers before further updates are dropped.
+pub const SUBSCRIBER_CAPACITY: usize = 64;
 
 /// Holds the state for the mDNS scanning service.
 ///
@@ -27,264 +87,4033 @@ pub struct MdnsState {
     pub devices: Arc<Mutex<HashMap<String, Device>>>,
     /// The handle for the asynchronous task that stops the scan after a timeout.
     pub timeout_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The sequence number assigned to the next emitted event. Reset at the start of each scan.
+    pub event_seq: Arc<AtomicU64>,
+    /// The context of the active scan, shared with the tasks processing mDNS events.
+    pub scan: Arc<Mutex<Option<ScanContext>>>,
+    /// The handle for the task enforcing the scan's maximum lifetime, if one was configured.
+    pub lifetime_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The handle for the task re-querying the network during a scan boost.
+    pub boost_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The handle for the task re-browsing on an exponential back-off in reliable mode.
+    pub rebrowse_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The handle for the task re-querying at the interval set by `query_interval_ms`.
+    pub query_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The handle for the task emitting `device-heartbeat` during a continuous scan.
+    pub heartbeat_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The handle for the task waiting out a scan's `start_delay_ms` before launching it.
+    pub start_delay_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// The configuration `start_scan` uses when the caller passes none.
+    pub default_config: ScanConfig,
+    /// The shortest duration, in seconds, a scan runs for; shorter ones are raised to it.
+    /// `DEFAULT_MIN_SCAN_DURATION_SECS` when `None`.
+    pub min_scan_duration_secs: Option<u64>,
+    /// A handle to the dedicated runtime scan tasks are spawned on, if one was configured.
+    pub runtime: Option<Handle>,
+    /// Why the last scan ended, or `None` if no scan has ended yet.
+    pub last_scan_reason: Arc<Mutex<ScanEndReason>>,
+    /// The summary of the last scan that ran, if any.
+    pub last_scan_report: Arc<Mutex<Option<ScanReport>>>,
+    /// Channels watching a single device's updates, keyed by the device's IP address.
+    pub watchers: Arc<Mutex<HashMap<String, Vec<Channel<Device>>>>>,
+    /// The channel of the scan started with `start_scan_with_channel`, sent every published
+    /// device until the scan stops.
+    pub scan_channel: Arc<Mutex<Option<Channel<ScanStreamMessage>>>>,
+    /// The Rust channels registered with `subscribe_devices`, sent every published device. A
+    /// synchronous mutex, so subscribing doesn't need an async context.
+    pub subscribers: Arc<std::sync::Mutex<Vec<mpsc::Sender<Device>>>>,
+    /// Whether device names and the aliases in `Device::hostnames` are passed through `clean_name`
+    /// before being stored. `Device::hostname` is kept as advertised, so it stays resolvable.
+    pub clean_names: bool,
+    /// The prefix emitted event names are given, followed by a colon. Bare names when `None`.
+    pub event_prefix: Option<String>,
+    /// How long `new-device` and `device-updated` events are coalesced for, per service type.
+    /// Unlisted types emit immediately.
+    pub debounce_windows: HashMap<String, Duration>,
+    /// The raw fields of the last resolution of each service instance, keyed by the IP address of
+    /// the device it belongs to.
+    pub raw_records: Arc<Mutex<HashMap<String, Vec<RawServiceRecord>>>>,
+    /// When a device was last published, keyed by its IP address, to space out its events.
+    pub last_emitted: Arc<Mutex<HashMap<String, Instant>>>,
+    /// The pending coalesced emissions and the event each will emit, keyed by the device's IP
+    /// address.
+    pub pending_emits: Arc<Mutex<HashMap<String, (&'static str, JoinHandle<()>)>>>,
+    /// The `(pattern, label)` rules classifying services of unrecognized types, in the order they
+    /// are tried.
+    pub classification_rules: Vec<(String, String)>,
+    /// The counters accumulated across scans, for diagnostics.
+    pub stats: Arc<Mutex<ScanStats>>,
+    /// Whether the running scan is paused, with its daemon shut down and its countdown frozen.
+    pub paused: Arc<Mutex<bool>>,
+    /// The countdown of the running scan, if it is timed, from which the time left is computed.
+    pub countdown: Arc<Mutex<Option<Countdown>>>,
+    /// The services advertised with `register_service`, keyed by fullname, kept while the scan is
+    /// paused so they are announced again when it resumes.
+    pub registrations: Arc<Mutex<HashMap<String, ServiceInfo>>>,
+    /// The handles for the tasks processing the daemon's browse receivers.
+    pub receiver_tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    /// The generation of the current scan, advanced whenever a scan starts or stops. Events
+    /// carrying another generation come from a previous scan and are dropped.
+    pub generation: Arc<AtomicU64>,
+    /// The id of the current or last scan, stamped on every event. A synchronous mutex, so events
+    /// can be emitted outside of an async context.
+    pub scan_id: Arc<std::sync::Mutex<Option<String>>>,
 }
 
-/// Resolves the `DeviceType` from the mDNS service type domain and fullname.
-fn resolve_device_type(ty_domain: &str, fullname: &str) -> Option<DeviceType> {
-    match ty_domain {
-        BLUESOUND_SERVICE_TYPE => Some(DeviceType::Bluesound),
-        VOLUMIO_SERVICE_TYPE if fullname.to_lowercase().contains("volumio") => {
-            Some(DeviceType::Volumio)
-        }
-        SPOTIFY_CONNECT_SERVICE_TYPE => Some(DeviceType::SpotifyConnect),
-        QOBUZ_CONNECT_SERVICE_TYPE => Some(DeviceType::QobuzConnect),
-        _ => Some(DeviceType::Generic),
+impl MdnsState {
+    /// Returns the id of the current or last scan, if any scan started yet.
+    fn scan_id(&self) -> Option<String> {
+        self.scan_id
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
-}
 
-/// Handles a resolved mDNS service, updating the device list and emitting an event.
-async fn handle_resolved_service<R: Runtime>(
-    info: Box<mdns_sd::ResolvedService>,
-    app_handle: AppHandle<R>,
-    seen_services: Arc<Mutex<HashSet<String>>>,
-    devices: Arc<Mutex<HashMap<String, Device>>>,
-    service_type: &str,
-    scan_start_time: Instant,
-) {
-    log::debug!(
-        "Addresses for {}: {:?}",
-        info.get_fullname(),
-        info.get_addresses()
-    );
-    let ip_option = info.get_addresses().iter().find_map(|addr| {
-        match addr.to_ip_addr() {
-            IpAddr::V4(ipv4_addr) if !ipv4_addr.is_link_local() => Some(IpAddr::V4(ipv4_addr)),
-            _ => None,
+    /// Returns the runtime to spawn scan tasks on, falling back to the ambient one.
+    fn runtime_handle(&self) -> Handle {
+        self.runtime.clone().unwrap_or_else(Handle::current)
+    }
+
+    /// Returns how long a scan given `duration_secs` actually runs, raised to the minimum scan
+    /// duration.
+    fn effective_duration_secs(&self, duration_secs: u64) -> u64 {
+        duration_secs.max(
+            self.min_scan_duration_secs
+                .unwrap_or(DEFAULT_MIN_SCAN_DURATION_SECS),
+        )
+    }
+
+    /// Aborts the tasks processing the daemon's browse receivers.
+    async fn abort_receiver_tasks(&self) {
+        for task in self.receiver_tasks.lock().await.drain(..) {
+            task.abort();
         }
-    });
+    }
 
-    let Some(ip) = ip_option else { return };
+    /// Withdraws every service advertised with `register_service` from `mdns`, before the daemon
+    /// shuts down.
+    async fn unregister_all(&self, mdns: &ServiceDaemon) {
+        for (fullname, _) in self.registrations.lock().await.drain() {
+            if let Err(e) = mdns.unregister(&fullname) {
+                log::warn!("Failed to unregister {}: {}", fullname, e);
+            }
+        }
+    }
 
-    let ip_string = ip.to_string();
-    let service_key = format!("{ip_string}|{service_type}");
-    if !seen_services.lock().await.insert(service_key) {
-        return;
+    /// Withdraws the registered services from the daemon of a scan being paused, remembering them
+    /// for `reregister_all`.
+    async fn withdraw_registrations(&self, mdns: &ServiceDaemon) {
+        for fullname in self.registrations.lock().await.keys() {
+            if let Err(e) = mdns.unregister(fullname) {
+                log::warn!("Failed to unregister {}: {}", fullname, e);
+            }
+        }
     }
 
-    let Some(device_type) = resolve_device_type(service_type, info.get_fullname()) else {
-        return;
-    };
+    /// Announces the services withdrawn by `withdraw_registrations` on the daemon of a resumed
+    /// scan.
+    async fn reregister_all(&self, mdns: &ServiceDaemon) {
+        for (fullname, info) in self.registrations.lock().await.iter() {
+            if let Err(e) = mdns.register(info.clone()) {
+                log::warn!("Failed to re-register {}: {}", fullname, e);
+            }
+        }
+    }
 
-    let name = info
-        .get_fullname()
-        .split('.')
-        .next()
-        .unwrap_or("")
-        .to_string();
-    let port = info.get_port();
-    let elapsed_ms = scan_start_time.elapsed().as_millis();
+    /// Withdraws the registered services and shuts the daemon down when the plugin is dropped.
+    ///
+    /// Runs outside of any async context, so state that is locked elsewhere is skipped.
+    pub(crate) fn teardown(&self) {
+        let Ok(mut daemon_guard) = self.daemon.try_lock() else {
+            return;
+        };
+        let Some(mdns) = daemon_guard.take() else {
+            return;
+        };
+        if let Ok(mut registrations_guard) = self.registrations.try_lock() {
+            for (fullname, _) in registrations_guard.drain() {
+                if let Err(e) = mdns.unregister(&fullname) {
+                    log::warn!("Failed to unregister {}: {}", fullname, e);
+                }
+            }
+        }
+        if let Err(e) = mdns.shutdown() {
+            log::error!("Failed to shutdown mDNS daemon: {}", e);
+        }
+    }
+}
 
-    log::info!(
-        "{} ({}:{}) {} ({}ms)",
-        name,
-        ip_string,
-        port,
-        service_type,
-        elapsed_ms
-    );
+/// The countdown of a timed scan. The time left is computed from when it started rather than
+/// kept by the ticking task, so it doesn't drift, and time spent paused doesn't count.
+#[derive(Debug, Clone, Copy)]
+pub struct Countdown {
+    started_at: Instant,
+    duration: Duration,
+    paused_for: Duration,
+    paused_at: Option<Instant>,
+}
 
-    let mut devices_guard = devices.lock().await;
-    let device_entry = devices_guard
-        .entry(ip_string.clone())
-        .or_insert_with(|| Device {
-            name: name.clone(),
-            ip: ip_string.clone(),
-            discovery_time_ms: elapsed_ms,
-            services: Vec::new(),
-        });
+impl Countdown {
+    fn new(started_at: Instant, duration: Duration) -> Self {
+        Self {
+            started_at,
+            duration,
+            paused_for: Duration::ZERO,
+            paused_at: None,
+        }
+    }
 
-    if elapsed_ms < device_entry.discovery_time_ms {
-        device_entry.discovery_time_ms = elapsed_ms;
+    /// Returns the time left at `now`, frozen at the time left when paused.
+    fn remaining_at(&self, now: Instant) -> Duration {
+        let now = self.paused_at.unwrap_or(now);
+        let elapsed = now
+            .saturating_duration_since(self.started_at)
+            .saturating_sub(self.paused_for);
+        self.duration.saturating_sub(elapsed)
     }
-    device_entry.name = name.clone();
 
-    device_entry.add_or_update_service(&service_type, port, device_type.clone(), elapsed_ms);
+    /// Returns the whole seconds left at `now`, rounded up so a scan doesn't report 0 until it
+    /// is over.
+    fn seconds_left_at(&self, now: Instant) -> u64 {
+        (self.remaining_at(now).as_millis() as u64).div_ceil(1000)
+    }
 
-    let device_payload = device_entry.clone();
-    drop(devices_guard);
+    fn pause(&mut self, now: Instant) {
+        self.paused_at.get_or_insert(now);
+    }
 
-    if let Err(e) = app_handle.emit("new-device", &device_payload) {
-        log::error!("Failed to emit new-device event: {}", e);
+    fn resume(&mut self, now: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_for += now.saturating_duration_since(paused_at);
+        }
     }
 }
 
-/// Processes events from a specific mDNS service receiver.
-async fn process_service_receiver<R: Runtime>(
-    receiver: mdns_sd::Receiver<ServiceEvent>,
-    app_handle: AppHandle<R>,
-    seen_services: Arc<Mutex<HashSet<String>>>,
-    devices: Arc<Mutex<HashMap<String, Device>>>,
-    service_type: String,
-    scan_start_time: Instant,
-) {
-    while let Ok(event) = receiver.recv_async().await {
-        if let ServiceEvent::ServiceResolved(info) = event {
-            handle_resolved_service(
-                info,
-                app_handle.clone(),
-                seen_services.clone(),
-                devices.clone(),
-                &service_type,
-                scan_start_time,
-            )
-            .await;
+/// Per-scan data shared by every task that browses for and processes mDNS services.
+#[derive(Clone)]
+pub struct ScanContext {
+    /// The service types being browsed.
+    pub service_types: Vec<String>,
+    /// The configuration the scan was started with.
+    pub config: Arc<ScanConfig>,
+    /// The `ip|service_type` keys already handled during this scan.
+    pub seen_services: Arc<Mutex<HashSet<String>>>,
+    /// The IP key of the device each resolved instance belongs to, keyed by the instance's
+    /// lowercased fullname.
+    pub instances: Arc<Mutex<HashMap<String, String>>>,
+    /// A handle to the shared map of discovered devices.
+    pub devices: Arc<Mutex<HashMap<String, Device>>>,
+    /// When the scan started.
+    pub start_time: Instant,
+    /// How long the scan runs before it stops automatically, in seconds, or `None` for a
+    /// continuous scan.
+    pub duration_secs: Option<u64>,
+    /// When a service was last added to the device map, or the start time if none was yet.
+    pub last_activity: Arc<Mutex<Instant>>,
+    /// The runtime the scan's tasks are spawned on.
+    pub runtime: Handle,
+    /// Whether device names and the aliases in `Device::hostnames` are passed through `clean_name`
+    /// before being stored. `Device::hostname` is kept as advertised, so it stays resolvable.
+    pub clean_names: bool,
+    /// The generation of the scan, as assigned from `MdnsState::generation` when it started.
+    pub generation: u64,
+    /// Whether `device-cap-reached` was emitted during this scan.
+    pub device_cap_reached: Arc<AtomicBool>,
+    /// The parsed `include_only` subnets of the configuration.
+    pub included: Arc<Vec<Subnet>>,
+    /// The parsed `exclude` subnets of the configuration.
+    pub excluded: Arc<Vec<Subnet>>,
+}
+
+/// Returns whether `ctx` belongs to the current scan, rather than to one that was stopped while
+/// its events were still being processed.
+fn is_current_scan<R: Runtime>(app: &AppHandle<R>, ctx: &ScanContext) -> bool {
+    app.state::<MdnsState>().generation.load(Ordering::SeqCst) == ctx.generation
+}
+
+/// Emits an event to the frontend under the configured prefix, stamping its payload with the next
+/// sequence number and the scan's id.
+fn emit_event<R: Runtime, S: Serialize + Clone>(
+    app: &AppHandle<R>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    let state = app.state::<MdnsState>();
+    let seq = state.event_seq.fetch_add(1, Ordering::SeqCst);
+    let scan_id = state.scan_id();
+    let event = match &state.event_prefix {
+        Some(prefix) => format!("{prefix}:{event}"),
+        None => event.to_string(),
+    };
+    app.emit(
+        &event,
+        SequencedEvent {
+            seq,
+            scan_id,
+            payload,
+        },
+    )
+}
+
+/// Emits a device event and forwards the device to the channels watching it.
+///
+/// Watchers whose channel can no longer be reached are dropped.
+async fn publish_device<R: Runtime>(app: &AppHandle<R>, event: &str, device: &Device) {
+    if let Err(e) = emit_event(app, event, device) {
+        log::error!("Failed to emit {} event: {}", event, e);
+    }
+
+    let state = app.state::<MdnsState>();
+    state
+        .last_emitted
+        .lock()
+        .await
+        .insert(device.ip.clone(), Instant::now());
+    let mut watchers_guard = state.watchers.lock().await;
+    if let Some(channels) = watchers_guard.get_mut(&device.ip) {
+        channels.retain(|channel| channel.send(device.clone()).is_ok());
+        if channels.is_empty() {
+            watchers_guard.remove(&device.ip);
         }
     }
-    log::info!("Receiver for {} disconnected.", service_type);
+    drop(watchers_guard);
+
+    let mut scan_channel_guard = state.scan_channel.lock().await;
+    if let Some(channel) = scan_channel_guard.as_ref() {
+        if let Err(e) = channel.send(ScanStreamMessage::Device(device.clone())) {
+            log::warn!("Dropping the scan channel after a failed send: {}", e);
+            scan_channel_guard.take();
+        }
+    }
+    drop(scan_channel_guard);
+
+    let mut subscribers_guard = state
+        .subscribers
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    subscribers_guard.retain(|sender| match sender.try_send(device.clone()) {
+        Ok(()) => true,
+        Err(mpsc::error::TrySendError::Full(_)) => {
+            log::warn!("Dropped {} update for a lagging subscriber", device.ip);
+            true
+        }
+        Err(mpsc::error::TrySendError::Closed(_)) => false,
+    });
 }
 
-/// Starts the LAN scan for mDNS services.
+/// Strips the trailing dot and the `.local` suffix from an instance name or hostname.
 ///
-/// This command initializes the mDNS daemon, browses for a predefined set of services,
-/// and spawns a timeout task to automatically stop the scan after 30 seconds.
-#[command]
-pub async fn start_scan<R: Runtime>(
-    app: AppHandle<R>,
-    state: State<'_, MdnsState>,
-) -> Result<(), String> {
-    log::info!("`start_scan` command called");
-    let mut scanning_guard = state.scanning.lock().await;
-    if *scanning_guard {
-        log::info!("Scan is already in progress.");
-        return Ok(());
+/// The suffix is matched case-insensitively, and only the last label is removed, so
+/// `living-room.local.` becomes `living-room` while `Living Room` is left untouched.
+fn clean_name(name: &str) -> String {
+    let name = name.trim_end_matches('.');
+    let suffix_start = name.len().saturating_sub(".local".len());
+    match name.get(suffix_start..) {
+        Some(suffix) if suffix_start > 0 && suffix.eq_ignore_ascii_case(".local") => {
+            name[..suffix_start].to_string()
+        }
+        _ => name.to_string(),
     }
-    *scanning_guard = true;
-    drop(scanning_guard);
+}
 
-    // Abort any existing timeout task to prevent multiple stop calls
-    if let Some(task) = state.timeout_task.lock().await.take() {
-        task.abort();
+/// Infers why the mDNS daemon couldn't be created from its error message, covering the
+/// messages and Windows socket error codes of the common cases.
+fn infer_daemon_error_cause(message: &str) -> DaemonErrorCause {
+    let message = message.to_lowercase();
+    if message.contains("address already in use")
+        || message.contains("addrinuse")
+        || message.contains("10048")
+    {
+        DaemonErrorCause::PortInUse
+    } else if message.contains("permission denied")
+        || message.contains("access is denied")
+        || message.contains("forbidden by its access permissions")
+        || message.contains("10013")
+    {
+        DaemonErrorCause::PermissionDenied
+    } else {
+        DaemonErrorCause::Unknown
     }
+}
 
-    log::info!("Starting LAN scan");
-
-    state.devices.lock().await.clear();
+/// Strips the subtype from a service type, so `_printer._sub._http._tcp.local.` and
+/// `_sub._printer._tcp.local.` become `_http._tcp.local.` and `_printer._tcp.local.`.
+fn base_service_type(service_type: &str) -> &str {
+    if let Some(index) = service_type.find("._sub.") {
+        &service_type[index + "._sub.".len()..]
+    } else {
+        service_type.strip_prefix("_sub.").unwrap_or(service_type)
+    }
+}
 
-    let mdns_result = ServiceDaemon::new();
+/// Returns whether any of the devices exposes a service of the given type, matched
+/// case-insensitively by its base type, as services are recorded under it.
+fn has_service_type<'a>(devices: impl IntoIterator<Item = &'a Device>, service_type: &str) -> bool {
+    let base_type = base_service_type(service_type);
+    devices.into_iter().any(|device| {
+        device
+            .services
+            .iter()
+            .any(|s| s.service_type.eq_ignore_ascii_case(base_type))
+    })
+}
 
-    let mdns = match mdns_result {
-        Ok(daemon) => daemon,
-        Err(e) => {
-            log::error!("Failed to create mDNS daemon: {}", e);
-            *state.scanning.lock().await = false;
-            return Err(format!("Failed to create mDNS daemon: {}", e));
+/// Resolves the `DeviceType` from the mDNS service type domain and fullname.
+///
+/// Subtypes are classified as their base service type.
+///
+/// Services the built-in mapping doesn't recognize are labeled by the first classification rule
+/// whose pattern the service type contains, and are `Generic` when none does.
+fn resolve_device_type(
+    ty_domain: &str,
+    fullname: &str,
+    rules: &[(String, String)],
+) -> Option<DeviceType> {
+    let ty_domain = base_service_type(ty_domain);
+    match ty_domain {
+        BLUESOUND_SERVICE_TYPE => Some(DeviceType::Bluesound),
+        VOLUMIO_SERVICE_TYPE if fullname.to_lowercase().contains("volumio") => {
+            Some(DeviceType::Volumio)
         }
+        SPOTIFY_CONNECT_SERVICE_TYPE => Some(DeviceType::SpotifyConnect),
+        QOBUZ_CONNECT_SERVICE_TYPE => Some(DeviceType::QobuzConnect),
+        ROON_SERVICE_TYPE => Some(DeviceType::Roon),
+        CHROMECAST_SERVICE_TYPE => Some(DeviceType::Chromecast),
+        _ => Some(
+            rules
+                .iter()
+                .find(|(pattern, _)| ty_domain.contains(pattern.as_str()))
+                .map_or(DeviceType::Generic, |(_, label)| {
+                    DeviceType::Custom(label.clone())
+                }),
+        ),
+    }
+}
+
+/// Returns a human-readable name for a service type, or the base type itself for types without
+/// a known name.
+fn service_display_name(service_type: &str) -> String {
+    let service_type = base_service_type(service_type);
+    match service_type {
+        BLUESOUND_SERVICE_TYPE => "Bluesound",
+        VOLUMIO_SERVICE_TYPE => "Web Service",
+        SPOTIFY_CONNECT_SERVICE_TYPE => "Spotify Connect",
+        QOBUZ_CONNECT_SERVICE_TYPE => "Qobuz Connect",
+        ROON_SERVICE_TYPE => "Roon",
+        CHROMECAST_SERVICE_TYPE => "Chromecast",
+        _ => service_type,
+    }
+    .to_string()
+}
+
+/// Returns the service types a scan with the given configuration browses: its `service_types`
+/// added to the built-in ones, or instead of them with `replace_service_types`.
+///
+/// Fails on the first configured type that doesn't end with `.local.`, or when nothing is left
+/// to browse.
+fn scan_service_types(config: &ScanConfig) -> Result<Vec<String>, ScanError> {
+    let mut service_types: Vec<String> = if config.replace_service_types {
+        Vec::new()
+    } else {
+        BUILTIN_SERVICE_TYPES
+            .iter()
+            .map(ToString::to_string)
+            .collect()
     };
+    for service_type in &config.service_types {
+        if !service_type.ends_with(".local.") {
+            return Err(ScanError::InvalidArgument(format!(
+                "Invalid service type '{service_type}': it must end with '.local.'"
+            )));
+        }
+        if !service_types.contains(service_type) {
+            service_types.push(service_type.clone());
+        }
+    }
+    if service_types.is_empty() {
+        return Err(ScanError::InvalidArgument(
+            "No service types to browse".to_string(),
+        ));
+    }
+    Ok(service_types)
+}
 
-    {
-        let mut daemon_guard = state.daemon.lock().await;
-        *daemon_guard = Some(mdns.clone());
+/// Resolves the network interfaces a scan is restricted to, given by name or IP address.
+///
+/// Fails listing every entry that matches no interface of this machine.
+fn scan_interfaces(interfaces: &[String]) -> Result<Vec<IfKind>, ScanError> {
+    if interfaces.is_empty() {
+        return Ok(Vec::new());
     }
+    let available = if_addrs::get_if_addrs().map_err(|e| {
+        ScanError::InvalidArgument(format!("Failed to list network interfaces: {e}"))
+    })?;
 
-    let scan_start_time = Instant::now();
-    let services_to_browse = vec![
-        BLUESOUND_SERVICE_TYPE.to_string(),
-        VOLUMIO_SERVICE_TYPE.to_string(),
-        SPOTIFY_CONNECT_SERVICE_TYPE.to_string(),
-        QOBUZ_CONNECT_SERVICE_TYPE.to_string(),
-    ];
-
-    let seen_services = Arc::new(Mutex::new(HashSet::new()));
-    let state_devices = state.devices.clone();
-
-    for service_type in services_to_browse {
-        log::debug!("Browsing for service type: {}", service_type);
-        let receiver = match mdns.browse(&service_type) {
-            Ok(rec) => rec,
-            Err(e) => {
-                log::error!("Failed to browse for service '{}': {}", service_type, e);
+    let mut kinds = Vec::new();
+    let mut unknown = Vec::new();
+    for interface in interfaces {
+        let kind = match interface.parse::<IpAddr>() {
+            Ok(addr) if available.iter().any(|i| i.ip() == addr) => IfKind::Addr(addr),
+            Err(_) if available.iter().any(|i| &i.name == interface) => {
+                IfKind::Name(interface.clone())
+            }
+            _ => {
+                unknown.push(interface.as_str());
                 continue;
             }
         };
-
-        tokio::spawn(process_service_receiver(
-            receiver,
-            app.clone(),
-            seen_services.clone(),
-            state_devices.clone(),
-            service_type,
-            scan_start_time,
-        ));
+        kinds.push(kind);
     }
+    if !unknown.is_empty() {
+        return Err(ScanError::InvalidArgument(format!(
+            "Unknown network interfaces: {}",
+            unknown.join(", ")
+        )));
+    }
+    Ok(kinds)
+}
 
-    let app_clone = app.clone();
-    let timeout_task = tokio::spawn(async move {
-        const SCAN_DURATION_SECS: u64 = 30;
-        for i in 0..SCAN_DURATION_SECS {
-            let seconds_left = SCAN_DURATION_SECS - i;
-            log::info!("Scan stopping in {} seconds...", seconds_left);
-            if let Err(e) = app_clone.emit("scan-tick", seconds_left) {
-                log::warn!("Failed to emit scan-tick event: {}", e);
+/// Decodes a service's TXT record properties, skipping values that aren't valid UTF-8.
+///
+/// Properties advertised without a value are kept with an empty one.
+fn decode_txt_properties(properties: &TxtProperties, fullname: &str) -> HashMap<String, String> {
+    let mut decoded = HashMap::new();
+    for property in properties.iter() {
+        let value = match property.val().map(std::str::from_utf8) {
+            None => String::new(),
+            Some(Ok(value)) => value.to_string(),
+            Some(Err(_)) => {
+                log::debug!(
+                    "Skipping non-UTF-8 TXT value of '{}' on {}",
+                    property.key(),
+                    fullname
+                );
+                continue;
             }
-            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-        }
+        };
+        decoded.insert(property.key().to_string(), value);
+    }
+    decoded
+}
 
-        log::info!("Scan timeout reached. Stopping scan automatically.");
-        let state_from_app = app_clone.state::<MdnsState>();
-        if let Err(e) = stop_scan(app_clone.clone(), state_from_app).await {
-            log::error!("Failed to stop scan automatically: {}", e);
+/// Captures the raw fields of a resolved service instance.
+fn raw_service_record(info: &mdns_sd::ResolvedService, resolved_at_ms: u128) -> RawServiceRecord {
+    let mut addresses: Vec<String> = info.get_addresses().iter().map(address_string).collect();
+    addresses.sort();
+    let txt = info
+        .get_properties()
+        .iter()
+        .map(|property| {
+            let value = property
+                .val()
+                .map(|value| String::from_utf8_lossy(value).into_owned());
+            (property.key().to_string(), value)
+        })
+        .collect();
+    RawServiceRecord {
+        fullname: info.get_fullname().to_string(),
+        service_type: info.ty_domain.clone(),
+        subtype: info.sub_ty_domain.clone(),
+        hostname: info.get_hostname().to_string(),
+        port: info.get_port(),
+        addresses,
+        txt,
+        resolved_at_ms,
+    }
+}
+
+/// Returns whether an IPv6 address is link-local (`fe80::/10`).
+fn is_ipv6_link_local(addr: &Ipv6Addr) -> bool {
+    addr.segments()[0] & 0xffc0 == 0xfe80
+}
+
+/// Returns whether an advertised address can be used to reach a device.
+///
+/// Link-local addresses are only usable with `allow_link_local`. Other IPv6 addresses are only
+/// usable with `include_ipv6`, and loopback, unspecified and multicast ones never are.
+fn is_usable_address(addr: &IpAddr, include_ipv6: bool, allow_link_local: bool) -> bool {
+    match addr {
+        IpAddr::V4(addr) => allow_link_local || !addr.is_link_local(),
+        IpAddr::V6(addr) if is_ipv6_link_local(addr) => allow_link_local,
+        IpAddr::V6(addr) => {
+            include_ipv6 && !addr.is_loopback() && !addr.is_unspecified() && !addr.is_multicast()
         }
-    });
+    }
+}
 
-    *state.timeout_task.lock().await = Some(timeout_task);
+/// An IP subnet in CIDR notation. A bare address is a subnet of that address alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subnet {
+    network: IpAddr,
+    prefix_len: u8,
+}
 
-    Ok(())
+impl Subnet {
+    /// Parses `10.0.0.0/24`, `fd00::/8` or a bare address, returning `None` if it's malformed or
+    /// the prefix length exceeds the address's bits.
+    fn parse(subnet: &str) -> Option<Self> {
+        let (network, prefix_len) = match subnet.trim().split_once('/') {
+            Some((network, prefix_len)) => (network.parse().ok()?, Some(prefix_len.parse().ok()?)),
+            None => (subnet.trim().parse().ok()?, None),
+        };
+        let max_len = if matches!(network, IpAddr::V4(_)) {
+            32
+        } else {
+            128
+        };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        (prefix_len <= max_len).then_some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Returns whether `addr` is within the subnet. Addresses of the other family never are.
+    fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*addr) & mask
+            }
+            _ => false,
+        }
+    }
 }
 
-/// Stops the LAN scan.
+/// Parses the subnets of the scan configuration's `option`.
 ///
-/// This command shuts down the mDNS daemon and aborts the scan timeout task.
-#[command]
-pub async fn stop_scan<R: Runtime>(
-    app: AppHandle<R>,
-    state: State<'_, MdnsState>,
-) -> Result<(), String> {
-    log::info!("Stopping LAN scan");
-    let mut scanning_guard = state.scanning.lock().await;
-    if !*scanning_guard {
-        log::info!("Scan is not running.");
-        return Ok(());
+/// Fails listing every entry that isn't an IP address or a CIDR subnet.
+fn parse_subnets(option: &str, subnets: &[String]) -> Result<Vec<Subnet>, ScanError> {
+    let mut parsed = Vec::new();
+    let mut malformed = Vec::new();
+    for subnet in subnets {
+        match Subnet::parse(subnet) {
+            Some(subnet) => parsed.push(subnet),
+            None => malformed.push(subnet.as_str()),
+        }
     }
-    *scanning_guard = false;
-    drop(scanning_guard);
+    if !malformed.is_empty() {
+        return Err(ScanError::InvalidArgument(format!(
+            "Malformed IP addresses or CIDR subnets in {}: {}",
+            option,
+            malformed.join(", ")
+        )));
+    }
+    Ok(parsed)
+}
 
-    // Abort the timeout task as it's no longer needed
-    if let Some(task) = state.timeout_task.lock().await.take() {
-        task.abort();
+/// Returns whether a scan with the given configuration keeps an advertised address: it must be
+/// of the scan's IP version and usable, with IPv6-only scans keeping IPv6 addresses as if
+/// `include_ipv6` were set.
+fn is_kept_address(addr: &IpAddr, config: &ScanConfig) -> bool {
+    match config.ip_version {
+        IpVersion::V4 if addr.is_ipv6() => false,
+        IpVersion::V6 if addr.is_ipv4() => false,
+        ip_version => is_usable_address(
+            addr,
+            config.include_ipv6 || ip_version == IpVersion::V6,
+            config.allow_link_local,
+        ),
     }
+}
 
-    if let Some(mdns) = state.daemon.lock().await.take() {
-        if let Err(e) = mdns.shutdown() {
-            log::error!("Failed to shutdown mDNS daemon: {}", e);
-            return Err(format!("Failed to shutdown mDNS daemon: {}", e));
+/// Ranks an address by how far it can be routed: global ones first, then private and
+/// unique-local ones, then link-local ones.
+fn address_scope_rank(addr: &IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(addr) if addr.is_link_local() => 2,
+        IpAddr::V4(addr) if addr.is_private() || addr.is_loopback() => 1,
+        IpAddr::V6(addr) if is_ipv6_link_local(addr) => 2,
+        IpAddr::V6(addr) if addr.segments()[0] & 0xfe00 == 0xfc00 || addr.is_loopback() => 1,
+        _ => 0,
+    }
+}
+
+/// Orders addresses by preference: by `address_scope_rank`, then IPv4 before IPv6, then by value,
+/// so a global address beats a link-local one of either family and the same addresses always
+/// yield the same primary one.
+fn address_preference(addr: &IpAddr) -> (u8, bool, IpAddr) {
+    (address_scope_rank(addr), addr.is_ipv6(), *addr)
+}
+
+/// Returns the addresses other than `ip`, in order of preference. Addresses that don't parse,
+/// which none of the recorded ones should, sort last.
+fn alternate_ips(ip: &str, addresses: &[String]) -> Vec<String> {
+    let mut alternates: Vec<String> = addresses.iter().filter(|a| *a != ip).cloned().collect();
+    alternates.sort_by_cached_key(|address| {
+        let without_zone = address.split('%').next().unwrap_or(address);
+        let preference = without_zone
+            .parse::<IpAddr>()
+            .ok()
+            .map(|a| address_preference(&a));
+        (preference.is_none(), preference, address.clone())
+    });
+    alternates
+}
+
+/// Formats an advertised address, keeping the zone of a link-local IPv6 address, e.g.
+/// `fe80::1%en0`, without which it can't be connected to.
+fn address_string(addr: &ScopedIp) -> String {
+    match addr {
+        ScopedIp::V6(v6) if is_ipv6_link_local(v6.addr()) => addr.to_string(),
+        _ => addr.to_ip_addr().to_string(),
+    }
+}
+
+/// Returns the socket address of a service advertised on `addr`, scoped to its interface for
+/// IPv6 addresses.
+fn service_socket_addr(addr: &ScopedIp, port: u16) -> SocketAddr {
+    match addr {
+        ScopedIp::V6(v6) => {
+            SocketAddr::V6(SocketAddrV6::new(*v6.addr(), port, 0, v6.scope_id().index))
         }
-        log::info!("mDNS daemon shut down.");
-        if let Err(e) = app.emit("scan-stopped", ()) {
-            log::error!("Failed to emit scan-stopped event: {}", e);
+        _ => SocketAddr::new(addr.to_ip_addr(), port),
+    }
+}
+
+/// Finds the IP key of a known device sharing at least one address with the given ones.
+fn find_alias_device(devices: &HashMap<String, Device>, addresses: &[String]) -> Option<String> {
+    devices
+        .values()
+        .find(|device| device.addresses.iter().any(|a| addresses.contains(a)))
+        .map(|device| device.ip.clone())
+}
+
+/// Finds the IP key of a known device with the given name, unless a device is known at `ip`.
+fn find_named_device(devices: &HashMap<String, Device>, name: &str, ip: &str) -> Option<String> {
+    if devices.contains_key(ip) {
+        return None;
+    }
+    devices
+        .values()
+        .find(|device| device.name == name)
+        .map(|device| device.ip.clone())
+}
+
+/// Counts the devices with at least one service of each device type, keyed by
+/// `DeviceType::label`.
+fn count_devices_per_type<'a>(
+    devices: impl IntoIterator<Item = &'a Device>,
+) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for device in devices {
+        for (i, service) in device.services.iter().enumerate() {
+            let counted = device.services[..i]
+                .iter()
+                .any(|s| s.device_type == service.device_type);
+            if !counted {
+                *counts
+                    .entry(service.device_type.label().to_string())
+                    .or_default() += 1;
+            }
         }
     }
-    Ok(())
+    counts
 }
 
-/// Checks if a scan is currently in progress.
-#[command]
-pub async fn is_scanning(state: State<'_, MdnsState>) -> Result<bool, String> {
-    Ok(*state.scanning.lock().await)
+/// Emits `type-counts` with the number of devices of each device type.
+fn emit_type_counts<R: Runtime>(app_handle: &AppHandle<R>, counts: HashMap<String, usize>) {
+    if let Err(e) = emit_event(app_handle, "type-counts", counts) {
+        log::error!("Failed to emit type-counts event: {}", e);
+    }
 }
 
-/// Returns the list of discovered devices.
-#[command]
-pub async fn get_discovered_devices(state: State<'_, MdnsState>) -> Result<Vec<Device>, String> {
-    let devices_guard = state.devices.lock().await;
-    Ok(devices_guard.values().cloned().collect())
+/// Counts the devices exposing a service of the given type.
+fn count_devices_with_service(devices: &HashMap<String, Device>, service_type: &str) -> usize {
+    devices
+        .values()
+        .filter(|device| {
+            device
+                .services
+                .iter()
+                .any(|s| s.service_type == service_type)
+        })
+        .count()
+}
+
+/// Moves a known device that reappeared at a new IP to its new key in the device map.
+///
+/// A device is considered the same when it shares the hostname, or the instance name if the
+/// hostname is unknown, and no device is known at the new IP yet. Returns the change, if any.
+fn relocate_device(
+    devices: &mut HashMap<String, Device>,
+    name: &str,
+    hostname: &str,
+    new_ip: &str,
+    addresses: &[String],
+) -> Option<DeviceIpChange> {
+    if devices.contains_key(new_ip) {
+        return None;
+    }
+    let old_ip = devices
+        .values()
+        .find(|device| {
+            if hostname.is_empty() {
+                device.name == name
+            } else {
+                device.hostnames.iter().any(|h| h == hostname)
+            }
+        })?
+        .ip
+        .clone();
+
+    let mut device = devices.remove(&old_ip)?;
+    device.ip = new_ip.to_string();
+    device.addresses = addresses.to_vec();
+    devices.insert(new_ip.to_string(), device);
+    Some(DeviceIpChange {
+        old_ip,
+        new_ip: new_ip.to_string(),
+    })
+}
+
+/// Handles a resolved mDNS service, updating the device list and emitting an event.
+///
+/// Returns the device the service belongs to, or `None` if the service was skipped.
+async fn handle_resolved_service<R: Runtime>(
+    info: Box<mdns_sd::ResolvedService>,
+    app_handle: &AppHandle<R>,
+    ctx: &ScanContext,
+    service_type: &str,
+) -> Option<Device> {
+    if !is_current_scan(app_handle, ctx) {
+        log::debug!("Dropping {} from a previous scan", info.get_fullname());
+        return None;
+    }
+    log::debug!(
+        "Addresses for {}: {:?}",
+        info.get_fullname(),
+        info.get_addresses()
+    );
+    let instance_label = info.get_fullname().split('.').next().unwrap_or("");
+    if let Some(prefix) = &ctx.config.instance_prefix {
+        if !instance_label.starts_with(prefix.as_str()) {
+            log::debug!("Skipping {}: no '{}' prefix", info.get_fullname(), prefix);
+            return None;
+        }
+    }
+    let mut addresses: Vec<&ScopedIp> = info
+        .get_addresses()
+        .iter()
+        .filter(|addr| is_kept_address(&addr.to_ip_addr(), &ctx.config))
+        .collect();
+    if !ctx.included.is_empty() {
+        addresses.retain(|addr| {
+            let addr = addr.to_ip_addr();
+            ctx.included.iter().any(|subnet| subnet.contains(&addr))
+        });
+        if addresses.is_empty() {
+            log::debug!(
+                "Skipping {}: no address in the included subnets",
+                info.get_fullname()
+            );
+            return None;
+        }
+    }
+    if let Some(addr) = addresses.iter().find(|addr| {
+        let addr = addr.to_ip_addr();
+        ctx.excluded.iter().any(|subnet| subnet.contains(&addr))
+    }) {
+        log::debug!("Skipping {}: {} is excluded", info.get_fullname(), addr);
+        return None;
+    }
+    // Globally routable addresses sort before private ones and private ones before link-local
+    // ones, whatever their family, so the device is keyed by its most reachable address. IPv4
+    // only wins among equally routable addresses.
+    addresses.sort_by_key(|addr| address_preference(&addr.to_ip_addr()));
+
+    let &first_address = addresses.first()?;
+    let addresses: Vec<String> = addresses.into_iter().map(address_string).collect();
+
+    let port = info.get_port();
+    if port == 0 && !ctx.config.keep_zero_port {
+        log::debug!("Skipping {} advertised on port 0", info.get_fullname());
+        return None;
+    }
+
+    let device_type = resolve_device_type(
+        service_type,
+        info.get_fullname(),
+        &app_handle.state::<MdnsState>().classification_rules,
+    )?;
+    if device_type == DeviceType::Generic
+        && ctx
+            .config
+            .ignore_generic_service_types
+            .iter()
+            .any(|t| t == service_type)
+    {
+        log::debug!("Skipping generic {}", info.get_fullname());
+        return None;
+    }
+
+    let mut name = instance_label.to_string();
+    let mut hostname = info.get_hostname().to_string();
+    if ctx.clean_names {
+        name = clean_name(&name);
+        hostname = clean_name(&hostname);
+    }
+
+    let mut ip_string = addresses[0].clone();
+    // With IPv6, the same host may advertise only some of its addresses on a service, so it is
+    // matched by address to avoid keying it twice.
+    if ctx.config.collapse_aliases || ctx.config.include_ipv6 {
+        if let Some(alias_ip) = find_alias_device(&*ctx.devices.lock().await, &addresses) {
+            ip_string = alias_ip;
+        }
+    }
+    let mut merged_ip = None;
+    if ctx.config.merge_by_name {
+        if let Some(named_ip) = find_named_device(&*ctx.devices.lock().await, &name, &ip_string) {
+            merged_ip = Some(std::mem::replace(&mut ip_string, named_ip));
+        }
+    }
+    let service_key = format!("{ip_string}|{service_type}");
+    let instance_key = info.get_fullname().to_lowercase();
+    if !ctx.seen_services.lock().await.insert(service_key) && !ctx.config.allow_reresolution {
+        let device = ctx.devices.lock().await.get(&ip_string).cloned();
+        if device.is_some() {
+            ctx.instances.lock().await.insert(instance_key, ip_string);
+        }
+        return device;
+    }
+
+    let elapsed_ms = ctx.start_time.elapsed().as_millis();
+
+    log::info!(
+        "{} ({}:{}) {} ({}ms)",
+        name,
+        ip_string,
+        port,
+        service_type,
+        elapsed_ms
+    );
+
+    let mut devices_guard = ctx.devices.lock().await;
+    let ip_change = if ctx.config.track_ip_changes {
+        relocate_device(&mut devices_guard, &name, &hostname, &ip_string, &addresses)
+    } else {
+        None
+    };
+    if let Some(limit) = ctx.config.per_type_limit {
+        if !devices_guard.contains_key(&ip_string)
+            && count_devices_with_service(&devices_guard, service_type) >= limit
+        {
+            log::debug!(
+                "Skipping {}: limit of {} reached for {}",
+                name,
+                limit,
+                service_type
+            );
+            return None;
+        }
+    }
+    if let Some(max_devices) = ctx.config.max_devices {
+        if !devices_guard.contains_key(&ip_string) && devices_guard.len() >= max_devices {
+            log::debug!("Skipping {}: cap of {} devices reached", name, max_devices);
+            drop(devices_guard);
+            if !ctx.device_cap_reached.swap(true, Ordering::SeqCst) {
+                log::warn!(
+                    "Device cap of {} reached, ignoring new devices",
+                    max_devices
+                );
+                if let Err(e) = emit_event(app_handle, "device-cap-reached", max_devices) {
+                    log::error!("Failed to emit device-cap-reached event: {}", e);
+                }
+            }
+            return None;
+        }
+    }
+    let is_new_device = !devices_guard.contains_key(&ip_string);
+    let device_entry = devices_guard
+        .entry(ip_string.clone())
+        .or_insert_with(|| Device {
+            name: name.clone(),
+            ip: ip_string.clone(),
+            discovery_time_ms: elapsed_ms,
+            hostname: String::new(),
+            hostnames: Vec::new(),
+            addresses: Vec::new(),
+            all_ips: Vec::new(),
+            additional_ips: Vec::new(),
+            services: Vec::new(),
+            tags: Vec::new(),
+            fingerprint: String::new(),
+            online: true,
+            mac: None,
+            vendor: None,
+            txt_keys: Vec::new(),
+            last_seen_at: Instant::now(),
+        });
+
+    if elapsed_ms < device_entry.discovery_time_ms {
+        device_entry.discovery_time_ms = elapsed_ms;
+    }
+    device_entry.name = name.clone();
+    let was_online = device_entry.online;
+    device_entry.online = true;
+    device_entry.last_seen_at = Instant::now();
+    if !info.get_hostname().is_empty() {
+        device_entry.hostname = info.get_hostname().to_string();
+    }
+    device_entry.add_aliases(&hostname, &addresses);
+    device_entry.all_ips = alternate_ips(&device_entry.ip, &device_entry.addresses);
+    if let Some(merged_ip) = merged_ip {
+        if !device_entry.additional_ips.contains(&merged_ip) {
+            device_entry.additional_ips.push(merged_ip);
+        }
+    }
+
+    let txt_properties = decode_txt_properties(info.get_properties(), info.get_fullname());
+    let service_changed = device_entry.add_or_update_service(
+        service_type,
+        port,
+        device_type.clone(),
+        elapsed_ms,
+        txt_properties,
+        service_display_name(service_type),
+    );
+    device_entry.set_service_fullname(service_type, info.get_fullname());
+    device_entry.add_txt_keys(info.get_properties().iter().map(|p| p.key()));
+    device_entry.update_fingerprint();
+
+    let device_payload = device_entry.clone();
+    let changed = is_new_device || service_changed || !was_online || ip_change.is_some();
+    let type_counts = changed.then(|| count_devices_per_type(devices_guard.values()));
+    drop(devices_guard);
+    let record = raw_service_record(&info, elapsed_ms);
+    let mut raw_records_guard = app_handle.state::<MdnsState>().raw_records.lock().await;
+    let records = raw_records_guard.entry(ip_string.clone()).or_default();
+    records.retain(|r| !r.fullname.eq_ignore_ascii_case(&record.fullname));
+    records.push(record);
+    drop(raw_records_guard);
+    if let Some(type_counts) = type_counts {
+        emit_type_counts(app_handle, type_counts);
+    }
+    if is_new_device {
+        app_handle
+            .state::<MdnsState>()
+            .stats
+            .lock()
+            .await
+            .devices_discovered += 1;
+    }
+    *ctx.last_activity.lock().await = Instant::now();
+    ctx.instances
+        .lock()
+        .await
+        .insert(instance_key, ip_string.clone());
+
+    if let Some(ip_change) = ip_change {
+        log::info!(
+            "{} moved from {} to {}",
+            name,
+            ip_change.old_ip,
+            ip_change.new_ip
+        );
+        if let Err(e) = emit_event(app_handle, "device-ip-changed", &ip_change) {
+            log::error!("Failed to emit device-ip-changed event: {}", e);
+        }
+        let state = app_handle.state::<MdnsState>();
+        let mut watchers_guard = state.watchers.lock().await;
+        if let Some(channels) = watchers_guard.remove(&ip_change.old_ip) {
+            watchers_guard
+                .entry(ip_change.new_ip.clone())
+                .or_default()
+                .extend(channels);
+        }
+        drop(watchers_guard);
+        for ip in ctx.instances.lock().await.values_mut() {
+            if *ip == ip_change.old_ip {
+                ip.clone_from(&ip_change.new_ip);
+            }
+        }
+    }
+
+    if changed {
+        // Only the first insertion is announced as new, so listeners can append on `new-device`
+        // and replace on `device-updated`.
+        let event = if is_new_device {
+            "new-device"
+        } else {
+            "device-updated"
+        };
+        let debounce_window = app_handle
+            .state::<MdnsState>()
+            .debounce_windows
+            .get(service_type)
+            .copied();
+        match debounce_window {
+            Some(window) if !window.is_zero() => {
+                let ip = device_payload.ip.clone();
+                schedule_device_event(app_handle, ctx, ip, window, event).await;
+            }
+            _ => throttle_device_event(app_handle, ctx, &device_payload, event).await,
+        }
+    } else {
+        let seen = DeviceSeen {
+            ip: device_payload.ip.clone(),
+            last_seen_ms: elapsed_ms,
+        };
+        if let Err(e) = emit_event(app_handle, "device-seen", seen) {
+            log::error!("Failed to emit device-seen event: {}", e);
+        }
+    }
+    if ctx.config.verify_reachability {
+        check_reachability(
+            app_handle,
+            ctx,
+            ip_string,
+            service_type.to_string(),
+            service_socket_addr(first_address, port),
+        );
+    }
+    if ctx.config.resolve_mac && changed && device_payload.mac.is_none() {
+        if let Ok(ipv4) = device_payload.ip.parse() {
+            resolve_mac(app_handle, ctx, device_payload.ip.clone(), ipv4);
+        }
+    }
+    Some(device_payload)
+}
+
+/// Looks up a device's MAC address in the ARP table in the background, records it in the
+/// device's `mac` along with its vendor, and emits the device through `device-updated` if that
+/// changed it.
+///
+/// The table only lists hosts the machine has exchanged traffic with, so a device that only
+/// multicast its announcements may not be found until something connects to it, e.g. a
+/// reachability check.
+fn resolve_mac<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    ctx: &ScanContext,
+    ip: String,
+    ipv4: Ipv4Addr,
+) {
+    let app = app_handle.clone();
+    let devices = ctx.devices.clone();
+    ctx.runtime.spawn(async move {
+        let Some(mac) = arp::lookup_mac(ipv4).await else {
+            log::debug!("No ARP entry for {}", ip);
+            return;
+        };
+
+        let mut devices_guard = devices.lock().await;
+        let Some(device) = devices_guard.get_mut(&ip) else {
+            return;
+        };
+        if device.mac.as_ref() == Some(&mac) {
+            return;
+        }
+        device.vendor = oui::lookup_vendor(&mac).map(str::to_string);
+        device.mac = Some(mac);
+        let device_payload = device.clone();
+        drop(devices_guard);
+        publish_device(&app, "device-updated", &device_payload).await;
+    });
+}
+
+/// Tries a TCP connection to a service in the background, records whether it succeeded in the
+/// service's `reachable`, and emits the device through `device-updated` if that changed it.
+fn check_reachability<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    ctx: &ScanContext,
+    ip: String,
+    service_type: String,
+    address: SocketAddr,
+) {
+    let app = app_handle.clone();
+    let devices = ctx.devices.clone();
+    let timeout = ctx
+        .config
+        .reachability_timeout_ms
+        .map_or(DEFAULT_REACHABILITY_TIMEOUT, Duration::from_millis);
+    ctx.runtime.spawn(async move {
+        let reachable = matches!(
+            tokio::time::timeout(timeout, TcpStream::connect(address)).await,
+            Ok(Ok(_))
+        );
+        log::debug!("{} ({}) reachable: {}", address, service_type, reachable);
+
+        let mut devices_guard = devices.lock().await;
+        let Some(device) = devices_guard.get_mut(&ip) else {
+            return;
+        };
+        let Some(service) = device
+            .services
+            .iter_mut()
+            .find(|s| s.service_type == service_type)
+        else {
+            return;
+        };
+        if service.reachable == Some(reachable) {
+            return;
+        }
+        service.reachable = Some(reachable);
+        let device_payload = device.clone();
+        drop(devices_guard);
+        publish_device(&app, "device-updated", &device_payload).await;
+    });
+}
+
+/// Emits `event` for the device at `ip` once `window` passes without another update to it.
+///
+/// Every call restarts the window, and the device is read when the window ends so the event
+/// carries all the services resolved meanwhile. A pending `new-device` stays one, since the
+/// device hasn't been announced yet.
+async fn schedule_device_event<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    ctx: &ScanContext,
+    ip: String,
+    window: Duration,
+    mut event: &'static str,
+) {
+    let state = app_handle.state::<MdnsState>();
+    let mut pending_guard = state.pending_emits.lock().await;
+    if let Some((previous_event, previous)) = pending_guard.remove(&ip) {
+        previous.abort();
+        if previous_event == "new-device" {
+            event = previous_event;
+        }
+    }
+
+    let app = app_handle.clone();
+    let devices = ctx.devices.clone();
+    let key = ip.clone();
+    let task = ctx.runtime.spawn(async move {
+        tokio::time::sleep(window).await;
+        let state = app.state::<MdnsState>();
+        state.pending_emits.lock().await.remove(&ip);
+        let device = devices.lock().await.get(&ip).cloned();
+        if let Some(device) = device {
+            publish_device(&app, event, &device).await;
+        }
+    });
+    pending_guard.insert(key, (event, task));
+}
+
+/// Publishes `event` for `device` right away, unless the device was published less than the
+/// scan's emit interval ago. The event is then deferred until the interval has passed, and
+/// carries the device's state at that point, so a burst of updates yields a single event.
+async fn throttle_device_event<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    ctx: &ScanContext,
+    device: &Device,
+    event: &'static str,
+) {
+    let interval = ctx
+        .config
+        .device_emit_interval_ms
+        .map_or(DEFAULT_DEVICE_EMIT_INTERVAL, Duration::from_millis);
+    let state = app_handle.state::<MdnsState>();
+    let last_emitted = state.last_emitted.lock().await.get(&device.ip).copied();
+    match emit_delay(last_emitted, Instant::now(), interval) {
+        Some(delay) => {
+            schedule_device_event(app_handle, ctx, device.ip.clone(), delay, event).await;
+        }
+        None => publish_device(app_handle, event, device).await,
+    }
+}
+
+/// Returns how long to defer an event for a device last published at `last_emitted` so events
+/// are at least `interval` apart, or `None` if it can be published right away.
+fn emit_delay(last_emitted: Option<Instant>, now: Instant, interval: Duration) -> Option<Duration> {
+    let next_allowed = last_emitted? + interval;
+    (next_allowed > now).then(|| next_allowed - now)
+}
+
+/// Handles a service instance going away, removing its service from the device it belongs to.
+///
+/// The service stays while another instance of the same type is known on the device. A device
+/// losing its last service is deleted and reported with `device-removed`, or kept and marked
+/// offline when the scan keeps offline devices. The service can be discovered again afterwards.
+async fn handle_removed_service<R: Runtime>(
+    fullname: &str,
+    app_handle: &AppHandle<R>,
+    ctx: &ScanContext,
+    service_type: &str,
+) {
+    if !is_current_scan(app_handle, ctx) {
+        log::debug!("Dropping the removal of {} from a previous scan", fullname);
+        return;
+    }
+    let mut instances_guard = ctx.instances.lock().await;
+    let Some(ip) = instances_guard.remove(&fullname.to_lowercase()) else {
+        return;
+    };
+    let type_suffix = service_type.to_lowercase();
+    let type_still_advertised = instances_guard
+        .iter()
+        .any(|(instance, other_ip)| *other_ip == ip && instance.ends_with(&type_suffix));
+    drop(instances_guard);
+    if type_still_advertised {
+        return;
+    }
+
+    log::info!("{} ({}) removed", fullname, ip);
+    ctx.seen_services
+        .lock()
+        .await
+        .remove(&format!("{ip}|{service_type}"));
+
+    let mut devices_guard = ctx.devices.lock().await;
+    let Some(device) = devices_guard.get_mut(&ip) else {
+        return;
+    };
+    if device
+        .services
+        .iter()
+        .any(|s| s.service_type != service_type)
+    {
+        device.services.retain(|s| s.service_type != service_type);
+        let device_payload = device.clone();
+        drop(devices_guard);
+        publish_device(app_handle, "device-updated", &device_payload).await;
+    } else {
+        drop(devices_guard);
+        retire_device(
+            app_handle,
+            &ip,
+            Some(service_type),
+            ctx.config.keep_offline_devices,
+        )
+        .await;
+    }
+    let type_counts = count_devices_per_type(ctx.devices.lock().await.values());
+    emit_type_counts(app_handle, type_counts);
+}
+
+/// Handles a device that is no longer advertising anything: with `keep_offline` it is marked
+/// offline and `device-updated` is emitted, otherwise it is removed along with its raw records and
+/// `device-removed` is emitted with `service_type`. Does nothing for an unknown device.
+///
+/// `type-counts` is left to the caller, which may retire several devices at once.
+async fn retire_device<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    ip: &str,
+    service_type: Option<&str>,
+    keep_offline: bool,
+) {
+    let state = app_handle.state::<MdnsState>();
+    let mut devices_guard = state.devices.lock().await;
+    if keep_offline {
+        let Some(device) = devices_guard.get_mut(ip) else {
+            return;
+        };
+        device.online = false;
+        let device_payload = device.clone();
+        drop(devices_guard);
+        publish_device(app_handle, "device-updated", &device_payload).await;
+        return;
+    }
+    if devices_guard.remove(ip).is_none() {
+        return;
+    }
+    drop(devices_guard);
+    state.raw_records.lock().await.remove(ip);
+    let removal = DeviceRemoved {
+        ip: ip.to_string(),
+        service_type: service_type.map(str::to_string),
+    };
+    if let Err(e) = emit_event(app_handle, "device-removed", removal) {
+        log::error!("Failed to emit device-removed event: {}", e);
+    }
+}
+
+/// Processes events from a specific mDNS service receiver.
+///
+/// Services browsed through a subtype are recorded under their base service type, so a device
+/// found through both isn't counted twice.
+async fn process_service_receiver<R: Runtime>(
+    receiver: mdns_sd::Receiver<ServiceEvent>,
+    app_handle: AppHandle<R>,
+    ctx: ScanContext,
+    service_type: String,
+) {
+    let base_type = base_service_type(&service_type);
+    while let Ok(event) = receiver.recv_async().await {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                handle_resolved_service(info, &app_handle, &ctx, base_type).await;
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                handle_removed_service(&fullname, &app_handle, &ctx, base_type).await;
+            }
+            _ => {}
+        }
+    }
+    log::info!("Receiver for {} disconnected.", service_type);
+}
+
+/// Records a task processing a browse receiver, so it is aborted when the scan stops, and forgets
+/// the ones that already finished.
+async fn track_receiver_task<R: Runtime>(app: &AppHandle<R>, task: JoinHandle<()>) {
+    let state = app.state::<MdnsState>();
+    let mut tasks_guard = state.receiver_tasks.lock().await;
+    tasks_guard.retain(|task| !task.is_finished());
+    tasks_guard.push(task);
+}
+
+/// Browses for a service type on the daemon and spawns a task processing its events.
+async fn browse_service_type<R: Runtime>(
+    mdns: &ServiceDaemon,
+    app: &AppHandle<R>,
+    ctx: &ScanContext,
+    service_type: &str,
+) -> Result<(), mdns_sd::Error> {
+    log::debug!("Browsing for service type: {}", service_type);
+    let receiver = mdns.browse(service_type)?;
+    let task = ctx.runtime.spawn(process_service_receiver(
+        receiver,
+        app.clone(),
+        ctx.clone(),
+        service_type.to_string(),
+    ));
+    track_receiver_task(app, task).await;
+    Ok(())
+}
+
+/// Browses again for a service type the daemon is already browsing, sending a fresh query. The
+/// running browse is stopped first, which disconnects its receiver and ends its task, instead of
+/// leaving a query chain and a task behind for every re-browse.
+async fn rebrowse_service_type<R: Runtime>(
+    mdns: &ServiceDaemon,
+    app: &AppHandle<R>,
+    ctx: &ScanContext,
+    service_type: &str,
+) -> Result<(), mdns_sd::Error> {
+    mdns.stop_browse(service_type)?;
+    browse_service_type(mdns, app, ctx, service_type).await
+}
+
+/// Browses for every service type of the scan, emitting `browse-error` for each one that fails.
+/// The scan proceeds with the service types that could be browsed.
+async fn browse_scan_service_types<R: Runtime>(
+    mdns: &ServiceDaemon,
+    app: &AppHandle<R>,
+    ctx: &ScanContext,
+) {
+    let mut failures = Vec::new();
+    for service_type in &ctx.service_types {
+        if let Err(e) = browse_service_type(mdns, app, ctx, service_type).await {
+            log::error!("Failed to browse for service '{}': {}", service_type, e);
+            failures.push((service_type.clone(), e.to_string()));
+        }
+    }
+    let any_succeeded = failures.len() < ctx.service_types.len();
+    for (service_type, message) in failures {
+        let error = BrowseError {
+            service_type,
+            message,
+            any_succeeded,
+        };
+        if let Err(e) = emit_event(app, "browse-error", error) {
+            log::error!("Failed to emit browse-error event: {}", e);
+        }
+    }
+}
+
+/// Re-browses every service type after 1, 2, 4, ... seconds, at most a minute apart, until
+/// `deadline` passes, catching devices that only announce periodically. The back-off is measured
+/// from when the task is spawned, i.e. from the scan's start or its resumption. Continuous scans,
+/// with no deadline, re-browse until they are stopped.
+async fn rebrowse_with_backoff<R: Runtime>(
+    mdns: ServiceDaemon,
+    app: AppHandle<R>,
+    ctx: ScanContext,
+    deadline: Option<Instant>,
+) {
+    let mut delay = Duration::from_secs(1);
+    while deadline.map_or(true, |deadline| Instant::now() + delay < deadline) {
+        tokio::time::sleep(delay).await;
+        log::debug!("Re-browsing after {:?}", delay);
+        for service_type in &ctx.service_types {
+            if let Err(e) = rebrowse_service_type(&mdns, &app, &ctx, service_type).await {
+                log::warn!("Failed to re-browse service '{}': {}", service_type, e);
+            }
+        }
+        delay = (delay * 2).min(MAX_REBROWSE_INTERVAL);
+    }
+}
+
+/// Re-browses every service type every `interval` until the scan stops, on top of the queries
+/// the daemon sends itself.
+///
+/// `mdns-sd` has no setting for its query cadence, so each tick re-browses the type with
+/// `rebrowse_service_type`, keeping a single receiver per type.
+async fn requery_periodically<R: Runtime>(
+    mdns: ServiceDaemon,
+    app: AppHandle<R>,
+    ctx: ScanContext,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately, and the scan has just browsed.
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        for service_type in &ctx.service_types {
+            if let Err(e) = rebrowse_service_type(&mdns, &app, &ctx, service_type).await {
+                log::warn!("Failed to re-query service '{}': {}", service_type, e);
+            }
+        }
+    }
+}
+
+/// Spawns `requery_periodically` if the scan configures a query interval, clamped to
+/// `MIN_QUERY_INTERVAL`.
+fn spawn_query_task<R: Runtime>(
+    mdns: &ServiceDaemon,
+    app: &AppHandle<R>,
+    ctx: &ScanContext,
+) -> Option<JoinHandle<()>> {
+    let interval = Duration::from_millis(ctx.config.query_interval_ms?).max(MIN_QUERY_INTERVAL);
+    log::info!("Re-querying every {:?}", interval);
+    Some(ctx.runtime.spawn(requery_periodically(
+        mdns.clone(),
+        app.clone(),
+        ctx.clone(),
+        interval,
+    )))
+}
+
+/// Spawns the task emitting `device-heartbeat` every `heartbeat_interval_secs` if the scan is
+/// continuous and configures one.
+fn spawn_heartbeat_task<R: Runtime>(
+    app: &AppHandle<R>,
+    ctx: &ScanContext,
+) -> Option<JoinHandle<()>> {
+    if ctx.duration_secs.is_some() {
+        return None;
+    }
+    let period = Duration::from_secs(ctx.config.heartbeat_interval_secs?.max(1));
+    let app = app.clone();
+    let devices = ctx.devices.clone();
+    Some(ctx.runtime.spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        // The first tick completes immediately, right as the scan starts.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let devices_guard = devices.lock().await;
+            let heartbeat = DeviceHeartbeat {
+                device_count: devices_guard.len(),
+                service_count: devices_guard.values().map(|d| d.services.len()).sum(),
+            };
+            drop(devices_guard);
+            if let Err(e) = emit_event(&app, "device-heartbeat", heartbeat) {
+                log::error!("Failed to emit device-heartbeat event: {}", e);
+            }
+        }
+    }))
+}
+
+/// Restricts the daemon to the given network interfaces, unless none are given, and to the
+/// interfaces of the given IP version.
+fn restrict_interfaces(mdns: &ServiceDaemon, interfaces: Vec<IfKind>, ip_version: IpVersion) {
+    if !interfaces.is_empty() {
+        log::info!("Restricting the scan to {:?}", interfaces);
+        if let Err(e) = mdns
+            .disable_interface(IfKind::All)
+            .and_then(|()| mdns.enable_interface(interfaces))
+        {
+            log::error!("Failed to select network interfaces: {}", e);
+        }
+    }
+    let other_version = match ip_version {
+        IpVersion::V4 => IfKind::IPv6,
+        IpVersion::V6 => IfKind::IPv4,
+        IpVersion::Both => return,
+    };
+    log::info!("Restricting the scan to {:?}", ip_version);
+    if let Err(e) = mdns.disable_interface(other_version) {
+        log::error!("Failed to select the IP version: {}", e);
+    }
+}
+
+/// Spawns the task counting down the last `seconds_left` seconds of a scan lasting
+/// `duration_secs`, emitting `scan-tick` and `scan-progress` every second, and stopping the scan
+/// once they elapse.
+fn spawn_timeout_task<R: Runtime>(
+    app: &AppHandle<R>,
+    runtime: &Handle,
+    duration_secs: u64,
+    seconds_left: u64,
+) -> JoinHandle<()> {
+    let app_clone = app.clone();
+    runtime.spawn(async move {
+        for seconds_left in (1..=seconds_left).rev() {
+            log::info!("Scan stopping in {} seconds...", seconds_left);
+            if let Err(e) = emit_event(&app_clone, "scan-tick", seconds_left) {
+                log::warn!("Failed to emit scan-tick event: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            let elapsed_secs = duration_secs - seconds_left + 1;
+            let progress = elapsed_secs as f64 / duration_secs as f64;
+            if let Err(e) = emit_event(&app_clone, "scan-progress", progress) {
+                log::warn!("Failed to emit scan-progress event: {}", e);
+            }
+        }
+
+        log::info!("Scan timeout reached. Stopping scan automatically.");
+        let state_from_app = app_clone.state::<MdnsState>();
+        // Detach this task's own handle so stopping the scan doesn't abort it midway.
+        state_from_app.timeout_task.lock().await.take();
+        if let Err(e) =
+            stop_scan_with_reason(&app_clone, &state_from_app, ScanEndReason::Timeout).await
+        {
+            log::error!("Failed to stop scan automatically: {}", e);
+        }
+    })
+}
+
+/// Starts the LAN scan for mDNS services.
+///
+/// This command initializes the mDNS daemon, browses for the built-in and configured services,
+/// and spawns a timeout task to automatically stop the scan after `duration_secs` seconds,
+/// or 30 seconds if none is given. Durations below the minimum scan duration, 3 seconds unless
+/// the builder sets another, are raised to it. Continuous scans ignore the duration and run until
+/// they are stopped.
+/// An optional `config` tweaks how discovered services are handled; without one,
+/// the plugin's default configuration is used.
+///
+/// Every event carries the scan's id, `scan_id` if given or a generated UUID otherwise, which is
+/// returned. If a scan is already running, its id is returned instead.
+///
+/// With `start_delay_ms`, the scan is marked as running and the id returned right away, but the
+/// daemon is only created, and `scan-started` emitted, once the delay has elapsed, e.g. to give
+/// the network interfaces time to come up on launch. Stopping the scan meanwhile cancels it.
+#[command]
+pub async fn start_scan<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    config: Option<ScanConfig>,
+    duration_secs: Option<u64>,
+    scan_id: Option<String>,
+    start_delay_ms: Option<u64>,
+) -> Result<String, ScanError> {
+    log::info!("`start_scan` command called");
+    let started = start_new_scan(
+        app,
+        &state,
+        config,
+        duration_secs,
+        scan_id,
+        start_delay_ms,
+        None,
+    )
+    .await?;
+    Ok(started.unwrap_or_else(|| state.scan_id().unwrap_or_default()))
+}
+
+/// Starts a scan like `start_scan`, streaming its devices down `channel` if given. Returns the new
+/// scan's id, or `None` without touching the running scan if there is one, so callers can't end
+/// up attached to a scan another caller started between their check and the start.
+async fn start_new_scan<R: Runtime>(
+    app: AppHandle<R>,
+    state: &MdnsState,
+    config: Option<ScanConfig>,
+    duration_secs: Option<u64>,
+    scan_id: Option<String>,
+    start_delay_ms: Option<u64>,
+    channel: Option<Channel<ScanStreamMessage>>,
+) -> Result<Option<String>, ScanError> {
+    let config = config.unwrap_or_else(|| state.default_config.clone());
+    let service_types = scan_service_types(&config)?;
+    let interfaces = scan_interfaces(&config.interfaces)?;
+    let included = parse_subnets("includeOnly", &config.include_only)?;
+    let excluded = parse_subnets("exclude", &config.exclude)?;
+    let mut scanning_guard = state.scanning.lock().await;
+    if *scanning_guard {
+        log::info!("Scan is already in progress.");
+        return Ok(None);
+    }
+    *scanning_guard = true;
+    *state.scan_channel.lock().await = channel;
+    drop(scanning_guard);
+    let scan_id = scan_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    *state
+        .scan_id
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(scan_id.clone());
+    state.event_seq.store(0, Ordering::SeqCst);
+
+    // Abort any existing timeout task to prevent multiple stop calls
+    if let Some(task) = state.timeout_task.lock().await.take() {
+        task.abort();
+    }
+
+    log::info!("Starting LAN scan");
+
+    // Free the daemon a previous scan kept alive when it went idle.
+    state.abort_receiver_tasks().await;
+    if let Some(mdns) = state.daemon.lock().await.take() {
+        state.unregister_all(&mdns).await;
+        if let Err(e) = mdns.shutdown() {
+            log::warn!("Failed to shutdown idle mDNS daemon: {}", e);
+        }
+    }
+
+    if !config.preserve_previous {
+        state.devices.lock().await.clear();
+        state.raw_records.lock().await.clear();
+        state.last_emitted.lock().await.clear();
+        if let Err(e) = emit_event(&app, "devices-cleared", ()) {
+            log::error!("Failed to emit devices-cleared event: {}", e);
+        }
+    }
+
+    match start_delay_ms.filter(|&delay_ms| delay_ms > 0) {
+        Some(delay_ms) => {
+            log::info!("Delaying the scan by {}ms", delay_ms);
+            let app_clone = app.clone();
+            let start_delay_task = state.runtime_handle().spawn(async move {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+
+                let state_from_app = app_clone.state::<MdnsState>();
+                // Detach this task's own handle so stopping the scan doesn't abort it midway.
+                state_from_app.start_delay_task.lock().await.take();
+                if !*state_from_app.scanning.lock().await {
+                    return;
+                }
+                let launched = launch_scan(
+                    app_clone.clone(),
+                    config,
+                    duration_secs,
+                    service_types,
+                    interfaces,
+                    included,
+                    excluded,
+                )
+                .await;
+                if let Err(e) = launched {
+                    log::error!("Failed to start delayed scan: {}", e);
+                }
+            });
+            *state.start_delay_task.lock().await = Some(start_delay_task);
+        }
+        None => {
+            let launched = launch_scan(
+                app,
+                config,
+                duration_secs,
+                service_types,
+                interfaces,
+                included,
+                excluded,
+            )
+            .await;
+            if launched.is_err() {
+                state.scan_channel.lock().await.take();
+            }
+            launched?
+        }
+    }
+
+    Ok(Some(scan_id))
+}
+
+/// Creates the daemon of a scan `start_scan` has marked as running, browses for its service types
+/// and spawns its timeout, lifetime and background tasks.
+async fn launch_scan<R: Runtime>(
+    app: AppHandle<R>,
+    config: ScanConfig,
+    duration_secs: Option<u64>,
+    service_types: Vec<String>,
+    interfaces: Vec<IfKind>,
+    included: Vec<Subnet>,
+    excluded: Vec<Subnet>,
+) -> Result<(), ScanError> {
+    let state = app.state::<MdnsState>();
+    let mut attempts = 0;
+    let mdns = loop {
+        attempts += 1;
+        match ServiceDaemon::new() {
+            Ok(daemon) => break daemon,
+            Err(e) if attempts < DAEMON_INIT_ATTEMPTS => {
+                log::warn!("Failed to create mDNS daemon, retrying: {}", e);
+                tokio::time::sleep(DAEMON_INIT_RETRY_DELAY).await;
+            }
+            Err(e) => {
+                log::error!("Failed to create mDNS daemon: {}", e);
+                *state.scanning.lock().await = false;
+                *state.last_scan_reason.lock().await = ScanEndReason::Error;
+                let message = e.to_string();
+                let error = DaemonError {
+                    cause: infer_daemon_error_cause(&message),
+                    message: message.clone(),
+                    attempts,
+                };
+                if let Err(e) = emit_event(&app, "daemon-error", error) {
+                    log::error!("Failed to emit daemon-error event: {}", e);
+                }
+                return Err(ScanError::DaemonInit(message));
+            }
+        }
+    };
+
+    restrict_interfaces(&mdns, interfaces, config.ip_version);
+
+    {
+        let mut daemon_guard = state.daemon.lock().await;
+        *daemon_guard = Some(mdns.clone());
+    }
+
+    let scan_duration_secs = (!config.continuous).then(|| {
+        let requested_secs = duration_secs.unwrap_or(DEFAULT_SCAN_DURATION_SECS);
+        let effective_secs = state.effective_duration_secs(requested_secs);
+        if effective_secs != requested_secs {
+            log::warn!(
+                "Scan duration of {}s is below the minimum, scanning for {}s",
+                requested_secs,
+                effective_secs
+            );
+        }
+        effective_secs
+    });
+    let scan_start_time = Instant::now();
+    let ctx = ScanContext {
+        service_types,
+        config: Arc::new(config),
+        seen_services: Arc::new(Mutex::new(HashSet::new())),
+        instances: Arc::new(Mutex::new(HashMap::new())),
+        devices: state.devices.clone(),
+        start_time: scan_start_time,
+        duration_secs: scan_duration_secs,
+        last_activity: Arc::new(Mutex::new(scan_start_time)),
+        runtime: state.runtime_handle(),
+        clean_names: state.clean_names,
+        generation: state.generation.fetch_add(1, Ordering::SeqCst) + 1,
+        device_cap_reached: Arc::new(AtomicBool::new(false)),
+        included: Arc::new(included),
+        excluded: Arc::new(excluded),
+    };
+
+    state.stats.lock().await.scans_started += 1;
+    let scan_started = ScanStarted {
+        duration_secs: ctx.duration_secs,
+        service_types: ctx.service_types.clone(),
+    };
+    if let Err(e) = emit_event(&app, "scan-started", scan_started) {
+        log::error!("Failed to emit scan-started event: {}", e);
+    }
+
+    browse_scan_service_types(&mdns, &app, &ctx).await;
+
+    if ctx.config.reliable {
+        let deadline = ctx
+            .duration_secs
+            .map(|secs| ctx.start_time + Duration::from_secs(secs));
+        let rebrowse_task = ctx.runtime.spawn(rebrowse_with_backoff(
+            mdns.clone(),
+            app.clone(),
+            ctx.clone(),
+            deadline,
+        ));
+        *state.rebrowse_task.lock().await = Some(rebrowse_task);
+    }
+    *state.query_task.lock().await = spawn_query_task(&mdns, &app, &ctx);
+    *state.heartbeat_task.lock().await = spawn_heartbeat_task(&app, &ctx);
+
+    let runtime = ctx.runtime.clone();
+    let max_lifetime_secs = ctx.config.max_lifetime_secs;
+    *state.scan.lock().await = Some(ctx);
+
+    *state.countdown.lock().await =
+        scan_duration_secs.map(|secs| Countdown::new(scan_start_time, Duration::from_secs(secs)));
+    if let Some(scan_duration_secs) = scan_duration_secs {
+        let timeout_task =
+            spawn_timeout_task(&app, &runtime, scan_duration_secs, scan_duration_secs);
+        *state.timeout_task.lock().await = Some(timeout_task);
+    } else {
+        log::info!("Scanning continuously until stopped.");
+    }
+
+    if let Some(max_lifetime_secs) = max_lifetime_secs {
+        let app_clone = app.clone();
+        let lifetime_task = runtime.spawn(async move {
+            tokio::time::sleep(Duration::from_secs(max_lifetime_secs)).await;
+
+            log::info!("Scan maximum lifetime reached. Stopping scan automatically.");
+            let state_from_app = app_clone.state::<MdnsState>();
+            // Detach this task's own handle so stopping the scan doesn't abort it midway.
+            state_from_app.lifetime_task.lock().await.take();
+            if let Err(e) =
+                stop_scan_with_reason(&app_clone, &state_from_app, ScanEndReason::MaxLifetime).await
+            {
+                log::error!("Failed to stop scan automatically: {}", e);
+            }
+        });
+        *state.lifetime_task.lock().await = Some(lifetime_task);
+    }
+
+    Ok(())
+}
+
+/// Stops the LAN scan.
+///
+/// This command shuts down the mDNS daemon and aborts the scan timeout task. It also frees the
+/// daemon of a scan that went idle on timeout.
+#[command]
+pub async fn stop_scan<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<(), ScanError> {
+    stop_scan_with_reason(&app, &state, ScanEndReason::User).await
+}
+
+/// Starts a LAN scan like `start_scan`, additionally streaming each found or updated device down
+/// `channel` instead of requiring event listeners.
+///
+/// The channel receives a `finished` message with the reason once the scan stops, after which it
+/// is dropped. Fails if a scan is already running, since its devices would bypass the channel.
+/// Returns the scan's id, as `start_scan` does.
+#[command]
+pub async fn start_scan_with_channel<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    channel: Channel<ScanStreamMessage>,
+    config: Option<ScanConfig>,
+    duration_secs: Option<u64>,
+    scan_id: Option<String>,
+) -> Result<String, ScanError> {
+    start_new_scan(
+        app,
+        &state,
+        config,
+        duration_secs,
+        scan_id,
+        None,
+        Some(channel),
+    )
+    .await?
+    .ok_or(ScanError::AlreadyScanning)
+}
+
+/// Pauses the running scan, e.g. while the user is on a bandwidth-sensitive screen.
+///
+/// The mDNS daemon is shut down and the countdown frozen, while the device list and the scan's
+/// seen services are kept for `resume_scan`. The scan still counts as running, so `is_scanning`
+/// keeps returning `true`. Pausing a paused scan does nothing.
+#[command]
+pub async fn pause_scan<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<(), ScanError> {
+    if !*state.scanning.lock().await {
+        return Err(ScanError::NotScanning);
+    }
+    let mut paused_guard = state.paused.lock().await;
+    if *paused_guard {
+        return Ok(());
+    }
+
+    log::info!("Pausing LAN scan");
+    for task in [
+        &state.timeout_task,
+        &state.boost_task,
+        &state.rebrowse_task,
+        &state.query_task,
+        &state.heartbeat_task,
+    ] {
+        if let Some(task) = task.lock().await.take() {
+            task.abort();
+        }
+    }
+    state.abort_receiver_tasks().await;
+    if let Some(mdns) = state.daemon.lock().await.take() {
+        state.withdraw_registrations(&mdns).await;
+        if let Err(e) = mdns.shutdown() {
+            log::error!("Failed to shutdown mDNS daemon: {}", e);
+            return Err(ScanError::Shutdown(e.to_string()));
+        }
+    }
+    if let Some(countdown) = state.countdown.lock().await.as_mut() {
+        countdown.pause(Instant::now());
+    }
+    *paused_guard = true;
+    drop(paused_guard);
+
+    if let Err(e) = emit_event(&app, "scan-paused", ()) {
+        log::error!("Failed to emit scan-paused event: {}", e);
+    }
+    Ok(())
+}
+
+/// Resumes a paused scan, browsing its service types on a new mDNS daemon and continuing the
+/// countdown where it was frozen. Resuming a scan that isn't paused does nothing.
+#[command]
+pub async fn resume_scan<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<(), ScanError> {
+    if !*state.scanning.lock().await {
+        return Err(ScanError::NotScanning);
+    }
+    let mut paused_guard = state.paused.lock().await;
+    if !*paused_guard {
+        return Ok(());
+    }
+    let Some(ctx) = state.scan.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+
+    log::info!("Resuming LAN scan");
+    let mdns = ServiceDaemon::new().map_err(|e| {
+        log::error!("Failed to create mDNS daemon: {}", e);
+        ScanError::DaemonInit(e.to_string())
+    })?;
+    restrict_interfaces(
+        &mdns,
+        scan_interfaces(&ctx.config.interfaces)?,
+        ctx.config.ip_version,
+    );
+    *state.daemon.lock().await = Some(mdns.clone());
+    *paused_guard = false;
+    drop(paused_guard);
+
+    let now = Instant::now();
+    let remaining = state.countdown.lock().await.as_mut().map(|countdown| {
+        countdown.resume(now);
+        (countdown.remaining_at(now), countdown.seconds_left_at(now))
+    });
+
+    state.reregister_all(&mdns).await;
+    browse_scan_service_types(&mdns, &app, &ctx).await;
+    *state.query_task.lock().await = spawn_query_task(&mdns, &app, &ctx);
+    *state.heartbeat_task.lock().await = spawn_heartbeat_task(&app, &ctx);
+    if ctx.config.reliable {
+        // The back-off starts over from the resumption, up to the time the scan has left.
+        let deadline = remaining.map(|(remaining, _)| now + remaining);
+        let rebrowse_task = ctx.runtime.spawn(rebrowse_with_backoff(
+            mdns,
+            app.clone(),
+            ctx.clone(),
+            deadline,
+        ));
+        *state.rebrowse_task.lock().await = Some(rebrowse_task);
+    }
+    if let (Some(duration_secs), Some((_, seconds_left))) = (ctx.duration_secs, remaining) {
+        let timeout_task = spawn_timeout_task(&app, &ctx.runtime, duration_secs, seconds_left);
+        *state.timeout_task.lock().await = Some(timeout_task);
+    }
+
+    if let Err(e) = emit_event(&app, "scan-resumed", ()) {
+        log::error!("Failed to emit scan-resumed event: {}", e);
+    }
+    Ok(())
+}
+
+/// Checks if the running scan is paused.
+#[command]
+pub async fn is_paused(state: State<'_, MdnsState>) -> Result<bool, ScanError> {
+    Ok(*state.paused.lock().await)
+}
+
+/// Stops the LAN scan if one is running, recording why it ended.
+async fn stop_scan_with_reason<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &MdnsState,
+    reason: ScanEndReason,
+) -> Result<(), ScanError> {
+    log::info!("Stopping LAN scan ({:?})", reason);
+    let mut scanning_guard = state.scanning.lock().await;
+    if !*scanning_guard {
+        drop(scanning_guard);
+        // A scan that went idle on timeout keeps its daemon until it is stopped explicitly.
+        state.scan.lock().await.take();
+        state.generation.fetch_add(1, Ordering::SeqCst);
+        state.abort_receiver_tasks().await;
+        if let Some(mdns) = state.daemon.lock().await.take() {
+            state.unregister_all(&mdns).await;
+            mdns.shutdown().map_err(|e| {
+                log::error!("Failed to shutdown mDNS daemon: {}", e);
+                ScanError::Shutdown(e.to_string())
+            })?;
+            log::info!("Idle mDNS daemon shut down.");
+        } else {
+            log::info!("Scan is not running.");
+        }
+        return Ok(());
+    }
+    *scanning_guard = false;
+    drop(scanning_guard);
+    // A scan that ran its full duration with its device cap reached says so, as its device list
+    // may be truncated.
+    let cap_reached = state
+        .scan
+        .lock()
+        .await
+        .as_ref()
+        .is_some_and(|ctx| ctx.device_cap_reached.load(Ordering::SeqCst));
+    let reason = match reason {
+        ScanEndReason::Timeout if cap_reached => ScanEndReason::Count,
+        reason => reason,
+    };
+    let ran_full_duration = matches!(reason, ScanEndReason::Timeout | ScanEndReason::Count);
+    *state.last_scan_reason.lock().await = reason.clone();
+    let was_paused = std::mem::take(&mut *state.paused.lock().await);
+
+    // Abort the timeout and boost tasks as they're no longer needed
+    if let Some(task) = state.timeout_task.lock().await.take() {
+        task.abort();
+    }
+    if let Some(task) = state.lifetime_task.lock().await.take() {
+        task.abort();
+    }
+    if let Some(task) = state.boost_task.lock().await.take() {
+        task.abort();
+    }
+    if let Some(task) = state.rebrowse_task.lock().await.take() {
+        task.abort();
+    }
+    if let Some(task) = state.query_task.lock().await.take() {
+        task.abort();
+    }
+    if let Some(task) = state.heartbeat_task.lock().await.take() {
+        task.abort();
+    }
+    if let Some(task) = state.start_delay_task.lock().await.take() {
+        task.abort();
+    }
+    // Drop the coalesced emissions still waiting, so they don't fire after `scan-stopped`.
+    for (_, (_, task)) in state.pending_emits.lock().await.drain() {
+        task.abort();
+    }
+    let ctx = state.scan.lock().await.take();
+    state.watchers.lock().await.clear();
+    if let Some(channel) = state.scan_channel.lock().await.take() {
+        if let Err(e) = channel.send(ScanStreamMessage::Finished(reason.clone())) {
+            log::warn!("Failed to send the end of the scan to its channel: {}", e);
+        }
+    }
+    let idle = ran_full_duration
+        && ctx
+            .as_ref()
+            .is_some_and(|ctx| ctx.config.on_timeout == TimeoutAction::Idle);
+
+    if let Some(ctx) = &ctx {
+        let duration_ms = ctx.start_time.elapsed().as_millis();
+        state.stats.lock().await.total_scan_time_ms += duration_ms;
+        let report = ScanReport::new(
+            state.devices.lock().await.values(),
+            duration_ms,
+            reason.clone(),
+        );
+        *state.last_scan_report.lock().await = Some(report.clone());
+        // Only a scan that ran its full duration completes, so the UI can tell it apart from
+        // one that was stopped.
+        if ran_full_duration {
+            if let Err(e) = emit_event(app, "scan-complete", report) {
+                log::error!("Failed to emit scan-complete event: {}", e);
+            }
+        }
+    }
+
+    if idle {
+        log::info!("Scan idle, keeping the mDNS daemon alive.");
+        *state.scan.lock().await = ctx;
+        if let Err(e) = emit_event(app, "scan-stopped", reason) {
+            log::error!("Failed to emit scan-stopped event: {}", e);
+        }
+        return Ok(());
+    }
+
+    // Drop the events of this scan still being processed, so they don't leak into the next one.
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    state.abort_receiver_tasks().await;
+    if let Some(mdns) = state.daemon.lock().await.take() {
+        state.unregister_all(&mdns).await;
+        if let Err(e) = mdns.shutdown() {
+            log::error!("Failed to shutdown mDNS daemon: {}", e);
+            return Err(ScanError::Shutdown(e.to_string()));
+        }
+        log::info!("mDNS daemon shut down.");
+    } else {
+        // A paused scan already withdrew its services; forget them so a later scan doesn't
+        // announce them again.
+        state.registrations.lock().await.clear();
+        if !was_paused {
+            return Ok(());
+        }
+    }
+    if let Err(e) = emit_event(app, "scan-stopped", reason) {
+        log::error!("Failed to emit scan-stopped event: {}", e);
+    }
+    Ok(())
+}
+
+/// Tears the scanner down regardless of whether a scan is marked as running, as a recovery hatch
+/// for when `stop_scan` returns early yet the daemon is still alive, e.g. after a task panicked.
+///
+/// Every scan task is aborted, the daemon is shut down if there is one, and the scan is marked as
+/// stopped, then `scan-stopped` is emitted. Calling it again is harmless.
+#[command]
+pub async fn force_shutdown<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<(), ScanError> {
+    log::warn!("Forcing the LAN scanner to shut down");
+    let was_scanning = std::mem::take(&mut *state.scanning.lock().await);
+    *state.paused.lock().await = false;
+    state.generation.fetch_add(1, Ordering::SeqCst);
+    for task in [
+        &state.timeout_task,
+        &state.lifetime_task,
+        &state.boost_task,
+        &state.rebrowse_task,
+        &state.query_task,
+        &state.heartbeat_task,
+        &state.start_delay_task,
+    ] {
+        if let Some(task) = task.lock().await.take() {
+            task.abort();
+        }
+    }
+    state.abort_receiver_tasks().await;
+    for (_, (_, task)) in state.pending_emits.lock().await.drain() {
+        task.abort();
+    }
+    state.scan.lock().await.take();
+    state.watchers.lock().await.clear();
+    if was_scanning {
+        *state.last_scan_reason.lock().await = ScanEndReason::User;
+    }
+    if let Some(channel) = state.scan_channel.lock().await.take() {
+        if let Err(e) = channel.send(ScanStreamMessage::Finished(ScanEndReason::User)) {
+            log::warn!("Failed to send the end of the scan to its channel: {}", e);
+        }
+    }
+    if let Some(mdns) = state.daemon.lock().await.take() {
+        state.unregister_all(&mdns).await;
+        // A wedged daemon may fail to acknowledge; it's dropped either way.
+        if let Err(e) = mdns.shutdown() {
+            log::warn!("Failed to shutdown mDNS daemon: {}", e);
+        }
+    }
+    state.registrations.lock().await.clear();
+
+    if let Err(e) = emit_event(&app, "scan-stopped", ScanEndReason::User) {
+        log::error!("Failed to emit scan-stopped event: {}", e);
+    }
+    Ok(())
+}
+
+/// Checks if a scan is currently in progress.
+#[command]
+pub async fn is_scanning(state: State<'_, MdnsState>) -> Result<bool, ScanError> {
+    Ok(*state.scanning.lock().await)
+}
+
+/// Sorts devices in the given order, breaking ties by IP so the order is deterministic.
+///
+/// IPs are compared numerically, and ones that don't parse sort last.
+fn sort_devices(devices: &mut [Device], sort_by: DeviceSort) {
+    let ip_key = |device: &Device| {
+        let ip = device.ip.parse::<IpAddr>().ok();
+        (ip.is_none(), ip, device.ip.clone())
+    };
+    match sort_by {
+        DeviceSort::DiscoveryTime => {
+            devices.sort_by_cached_key(|device| (device.discovery_time_ms, ip_key(device)));
+        }
+        DeviceSort::Name => {
+            devices.sort_by_cached_key(|device| (device.name.to_lowercase(), ip_key(device)));
+        }
+        DeviceSort::Ip => devices.sort_by_cached_key(ip_key),
+    }
+}
+
+/// Returns the list of discovered devices, sorted by `sort_by`, or by discovery time if not given.
+#[command]
+pub async fn get_discovered_devices(
+    state: State<'_, MdnsState>,
+    sort_by: Option<DeviceSort>,
+) -> Result<Vec<Device>, ScanError> {
+    let mut devices: Vec<Device> = state.devices.lock().await.values().cloned().collect();
+    sort_devices(&mut devices, sort_by.unwrap_or_default());
+    Ok(devices)
+}
+
+/// Whether a device whose services were last seen `last_seen_ms` into the scan has gone unseen for
+/// longer than `stale_after_ms`, `elapsed_ms` into the scan.
+fn is_stale(last_seen_ms: u128, elapsed_ms: u128, stale_after_ms: u128) -> bool {
+    elapsed_ms.saturating_sub(last_seen_ms) > stale_after_ms
+}
+
+/// Retrieves the discovered devices, earliest discovered first, each flagged `stale` if none of
+/// its services was seen in the last `stale_after_ms`. `last_seen_ms` counts from the start of
+/// the active scan; once the scan has stopped, the time each device was last resolved is used.
+#[command]
+pub async fn get_devices_with_freshness(
+    state: State<'_, MdnsState>,
+    stale_after_ms: u128,
+) -> Result<Vec<DeviceFreshness>, ScanError> {
+    let scan_elapsed_ms = state
+        .scan
+        .lock()
+        .await
+        .as_ref()
+        .map(|ctx| ctx.start_time.elapsed().as_millis());
+    let mut devices: Vec<Device> = state.devices.lock().await.values().cloned().collect();
+    sort_devices(&mut devices, DeviceSort::default());
+    Ok(devices
+        .into_iter()
+        .map(|device| {
+            let stale = match scan_elapsed_ms {
+                Some(elapsed_ms) => {
+                    let last_seen_ms = device
+                        .services
+                        .iter()
+                        .map(|s| s.last_seen_ms)
+                        .max()
+                        .unwrap_or(device.discovery_time_ms);
+                    is_stale(last_seen_ms, elapsed_ms, stale_after_ms)
+                }
+                None => device.last_seen_at.elapsed().as_millis() > stale_after_ms,
+            };
+            DeviceFreshness { device, stale }
+        })
+        .collect())
+}
+
+/// Emits `new-device` for every known device, earliest discovered first, so a listener mounted
+/// mid-scan can populate itself like it handles live discovery. The scan itself is left as is.
+#[command]
+pub async fn replay_devices<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<(), ScanError> {
+    let mut devices: Vec<Device> = state.devices.lock().await.values().cloned().collect();
+    sort_devices(&mut devices, DeviceSort::DiscoveryTime);
+    for device in &devices {
+        if let Err(e) = emit_event(&app, "new-device", device) {
+            log::error!("Failed to emit new-device event: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Empties the device list and emits `devices-cleared`, whether or not a scan is running.
+///
+/// A running scan keeps going with its services still marked as seen, so cleared devices only
+/// come back once they are rediscovered by a later scan.
+#[command]
+pub async fn clear_devices<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<(), ScanError> {
+    state.devices.lock().await.clear();
+    state.raw_records.lock().await.clear();
+    if let Err(e) = emit_event(&app, "devices-cleared", ()) {
+        log::error!("Failed to emit devices-cleared event: {}", e);
+    }
+    Ok(())
+}
+
+/// Retrieves the raw fields of the last resolution of each service instance on the device with
+/// the given IP, for debugging its classification.
+#[command]
+pub async fn get_raw_records(
+    state: State<'_, MdnsState>,
+    ip: String,
+) -> Result<Vec<RawServiceRecord>, ScanError> {
+    if !state.devices.lock().await.contains_key(&ip) {
+        return Err(ScanError::DeviceNotFound(ip));
+    }
+    let mut records = state
+        .raw_records
+        .lock()
+        .await
+        .get(&ip)
+        .cloned()
+        .unwrap_or_default();
+    records.sort_by(|a, b| a.fullname.cmp(&b.fullname));
+    Ok(records)
+}
+
+/// Retrieves the discovered device with the given IP, or `None` if no device is known there.
+#[command]
+pub async fn get_device_by_ip(
+    state: State<'_, MdnsState>,
+    ip: String,
+) -> Result<Option<Device>, ScanError> {
+    Ok(state.devices.lock().await.get(&ip).cloned())
+}
+
+/// Returns the configuration `start_scan` uses when called without one, with its service types
+/// resolved against the built-in ones and the duration a scan started without one runs for.
+///
+/// `replace_service_types` is set, so passing the configuration back to `start_scan` browses
+/// exactly the listed service types.
+#[command]
+pub async fn get_default_config(
+    state: State<'_, MdnsState>,
+) -> Result<DefaultScanConfig, ScanError> {
+    let mut config = state.default_config.clone();
+    config.service_types = scan_service_types(&config)?;
+    config.replace_service_types = true;
+    let duration_secs =
+        (!config.continuous).then(|| state.effective_duration_secs(DEFAULT_SCAN_DURATION_SECS));
+    Ok(DefaultScanConfig {
+        config,
+        duration_secs,
+    })
+}
+
+/// Returns the effective settings of the running scan, or those a `start_scan` call without
+/// arguments would use when no scan is running.
+#[command]
+pub async fn get_current_scan_config(
+    state: State<'_, MdnsState>,
+) -> Result<CurrentScanConfig, ScanError> {
+    if *state.scanning.lock().await {
+        if let Some(ctx) = state.scan.lock().await.as_ref() {
+            return Ok(CurrentScanConfig {
+                active: true,
+                duration_secs: ctx.duration_secs,
+                service_types: ctx.service_types.clone(),
+                include_ipv6: ctx.config.include_ipv6,
+                continuous: ctx.config.continuous,
+            });
+        }
+    }
+    let config = &state.default_config;
+    Ok(CurrentScanConfig {
+        active: false,
+        duration_secs: (!config.continuous)
+            .then(|| state.effective_duration_secs(DEFAULT_SCAN_DURATION_SECS)),
+        service_types: scan_service_types(config)?,
+        include_ipv6: config.include_ipv6,
+        continuous: config.continuous,
+    })
+}
+
+/// Returns the seconds left before the running scan stops, rounded up, or `None` if no scan is
+/// running, it is continuous, or it is still waiting out its `start_delay_ms`. A paused scan
+/// reports the seconds its countdown was frozen at.
+#[command]
+pub async fn get_scan_time_remaining(
+    state: State<'_, MdnsState>,
+) -> Result<Option<u64>, ScanError> {
+    if !*state.scanning.lock().await {
+        return Ok(None);
+    }
+    if state.scan.lock().await.is_none() {
+        return Ok(None);
+    }
+    Ok(state
+        .countdown
+        .lock()
+        .await
+        .map(|countdown| countdown.seconds_left_at(Instant::now())))
+}
+
+/// Returns the devices that advertise at least one service on the given port.
+#[command]
+pub async fn get_devices_by_port(
+    state: State<'_, MdnsState>,
+    port: u16,
+) -> Result<Vec<Device>, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard
+        .values()
+        .filter(|device| device.services.iter().any(|s| s.port == port))
+        .cloned()
+        .collect())
+}
+
+/// Returns whether any discovered device exposes a service of the given type. Subtypes match
+/// their base type.
+#[command]
+pub async fn has_device_of_service_type(
+    state: State<'_, MdnsState>,
+    service_type: String,
+) -> Result<bool, ScanError> {
+    Ok(has_service_type(
+        state.devices.lock().await.values(),
+        &service_type,
+    ))
+}
+
+/// Returns the number of devices with at least one service of each device type, as the
+/// `type-counts` event reports them.
+#[command]
+pub async fn get_type_counts(
+    state: State<'_, MdnsState>,
+) -> Result<HashMap<String, usize>, ScanError> {
+    Ok(count_devices_per_type(state.devices.lock().await.values()))
+}
+
+/// Returns the devices with at least one service classified as the given type.
+#[command]
+pub async fn get_devices_by_type(
+    state: State<'_, MdnsState>,
+    device_type: DeviceType,
+) -> Result<Vec<Device>, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard
+        .values()
+        .filter(|device| device.has_device_type(&device_type))
+        .cloned()
+        .collect())
+}
+
+/// Retrieves the discovered devices that expose the service type `has` but not `lacks`.
+#[command]
+pub async fn devices_with_service_not_other(
+    state: State<'_, MdnsState>,
+    has: String,
+    lacks: String,
+) -> Result<Vec<Device>, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard
+        .values()
+        .filter(|device| {
+            device.services.iter().any(|s| s.service_type == has)
+                && !device.services.iter().any(|s| s.service_type == lacks)
+        })
+        .cloned()
+        .collect())
+}
+
+/// Retrieves the discovered devices exposing at least `min_services` services, those with the most
+/// services first.
+#[command]
+pub async fn get_multiservice_devices(
+    state: State<'_, MdnsState>,
+    min_services: usize,
+) -> Result<Vec<Device>, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    let mut devices: Vec<Device> = devices_guard
+        .values()
+        .filter(|device| device.services.len() >= min_services)
+        .cloned()
+        .collect();
+    devices.sort_by_key(|device| std::cmp::Reverse(device.services.len()));
+    Ok(devices)
+}
+
+/// Groups the IPs of devices sharing a name, keeping only names shared by several IPs. Names and
+/// IPs are sorted so the result is deterministic.
+fn name_conflicts<'a>(devices: impl IntoIterator<Item = &'a Device>) -> Vec<(String, Vec<String>)> {
+    let mut ips_by_name: HashMap<&str, Vec<String>> = HashMap::new();
+    for device in devices {
+        ips_by_name
+            .entry(device.name.as_str())
+            .or_default()
+            .push(device.ip.clone());
+    }
+    let mut conflicts: Vec<(String, Vec<String>)> = ips_by_name
+        .into_iter()
+        .filter(|(_, ips)| ips.len() > 1)
+        .map(|(name, mut ips)| {
+            ips.sort_by_cached_key(|ip| (ip.parse::<IpAddr>().ok(), ip.clone()));
+            (name.to_string(), ips)
+        })
+        .collect();
+    conflicts.sort();
+    conflicts
+}
+
+/// Retrieves the names shared by devices at different IPs, e.g. two devices both advertised as
+/// "Kitchen", each with the IPs sharing it.
+#[command]
+pub async fn get_name_conflicts(
+    state: State<'_, MdnsState>,
+) -> Result<Vec<(String, Vec<String>)>, ScanError> {
+    Ok(name_conflicts(state.devices.lock().await.values()))
+}
+
+/// Returns whether the device meets every criterion of the filter.
+fn device_matches(device: &Device, filter: &DeviceFilter, subnet: Option<Subnet>) -> bool {
+    if let Some(types) = &filter.types {
+        if !types.iter().any(|t| device.has_device_type(t)) {
+            return false;
+        }
+    }
+    if let Some(service_types) = &filter.service_types {
+        if !device
+            .services
+            .iter()
+            .any(|s| service_types.contains(&s.service_type))
+        {
+            return false;
+        }
+    }
+    if let Some(name_contains) = &filter.name_contains {
+        if !device
+            .name
+            .to_lowercase()
+            .contains(&name_contains.to_lowercase())
+        {
+            return false;
+        }
+    }
+    if let Some(subnet) = subnet {
+        if !std::iter::once(&device.ip)
+            .chain(&device.addresses)
+            .filter_map(|ip| ip.parse::<IpAddr>().ok())
+            .any(|ip| subnet.contains(&ip))
+        {
+            return false;
+        }
+    }
+    if let Some(port) = filter.port {
+        if !device.services.iter().any(|s| s.port == port) {
+            return false;
+        }
+    }
+    if let Some(seen_since_ms) = filter.seen_since_ms {
+        if !device
+            .services
+            .iter()
+            .any(|s| s.last_seen_ms >= u128::from(seen_since_ms))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Retrieves the discovered devices matching every criterion of `filter`.
+#[command]
+pub async fn query_devices(
+    state: State<'_, MdnsState>,
+    filter: DeviceFilter,
+) -> Result<Vec<Device>, ScanError> {
+    let subnet = filter
+        .subnet
+        .as_deref()
+        .map(|subnet| {
+            Subnet::parse(subnet)
+                .ok_or_else(|| ScanError::InvalidArgument(format!("Invalid subnet '{subnet}'")))
+        })
+        .transpose()?;
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard
+        .values()
+        .filter(|device| device_matches(device, &filter, subnet))
+        .cloned()
+        .collect())
+}
+
+/// Retrieves the discovered device with the given fingerprint, if any.
+#[command]
+pub async fn get_device_by_fingerprint(
+    state: State<'_, MdnsState>,
+    fingerprint: String,
+) -> Result<Option<Device>, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard
+        .values()
+        .find(|device| device.fingerprint == fingerprint)
+        .cloned())
+}
+
+/// Temporarily boosts scan aggressiveness.
+///
+/// For the next `duration_secs` seconds, every browsed service type is re-queried once per second
+/// on the active daemon, on top of the daemon's own back-off schedule. Emits `scan-boosted` when the
+/// boost starts and `scan-boost-ended` once it elapses. Boosting again restarts the window.
+#[command]
+pub async fn boost_scan<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    duration_secs: u64,
+) -> Result<(), ScanError> {
+    if !*state.scanning.lock().await {
+        return Err(ScanError::NotScanning);
+    }
+    let Some(mdns) = state.daemon.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+    let Some(ctx) = state.scan.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+
+    if let Some(task) = state.boost_task.lock().await.take() {
+        task.abort();
+    }
+
+    log::info!("Boosting scan for {} seconds", duration_secs);
+    if let Err(e) = emit_event(&app, "scan-boosted", duration_secs) {
+        log::warn!("Failed to emit scan-boosted event: {}", e);
+    }
+
+    let app_clone = app.clone();
+    let runtime = ctx.runtime.clone();
+    let boost_task = runtime.spawn(async move {
+        for _ in 0..duration_secs {
+            for service_type in &ctx.service_types {
+                if let Err(e) = rebrowse_service_type(&mdns, &app_clone, &ctx, service_type).await {
+                    log::warn!("Failed to re-query service '{}': {}", service_type, e);
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        log::info!("Scan boost ended.");
+        if let Err(e) = emit_event(&app_clone, "scan-boost-ended", ()) {
+            log::warn!("Failed to emit scan-boost-ended event: {}", e);
+        }
+    });
+
+    *state.boost_task.lock().await = Some(boost_task);
+
+    Ok(())
+}
+
+/// Re-browses the service types of the device with the given IP on the active daemon, refreshing
+/// its services without restarting the scan.
+///
+/// The device's services are forgotten by the scan first, so as they resolve again their port and
+/// `last_seen_ms` are updated and the device is emitted through `device-updated` as usual.
+#[command]
+pub async fn rescan_device<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    ip: String,
+) -> Result<(), ScanError> {
+    let Some(mdns) = state.daemon.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+    let Some(ctx) = state.scan.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+    let service_types: Vec<String> = match state.devices.lock().await.get(&ip) {
+        Some(device) => device
+            .services
+            .iter()
+            .map(|s| s.service_type.clone())
+            .collect(),
+        None => return Err(ScanError::DeviceNotFound(ip)),
+    };
+
+    log::info!("Rescanning {} for {:?}", ip, service_types);
+    let mut seen_guard = ctx.seen_services.lock().await;
+    for service_type in &service_types {
+        seen_guard.remove(&format!("{ip}|{service_type}"));
+    }
+    drop(seen_guard);
+
+    for service_type in &service_types {
+        rebrowse_service_type(&mdns, &app, &ctx, service_type)
+            .await
+            .map_err(|e| ScanError::BrowseFailed {
+                service_type: service_type.clone(),
+                reason: e.to_string(),
+            })?;
+    }
+    Ok(())
+}
+
+/// Returns the discovered devices in the compact binary format described in the `packed` module.
+///
+/// The bytes are delivered to the frontend as a raw `ArrayBuffer`, skipping JSON entirely.
+#[command]
+pub async fn get_devices_packed(state: State<'_, MdnsState>) -> Result<Response, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    Ok(Response::new(pack_devices(devices_guard.values())))
+}
+
+/// Returns the device of the given type that was discovered first, i.e. with the lowest
+/// `discovery_time_ms`, or `None` if no such device has been found.
+#[command]
+pub async fn get_fastest_device_of_type(
+    state: State<'_, MdnsState>,
+    device_type: DeviceType,
+) -> Result<Option<Device>, ScanError> {
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard
+        .values()
+        .filter(|device| device.has_device_type(&device_type))
+        .min_by_key(|device| device.discovery_time_ms)
+        .cloned())
+}
+
+/// Scans until the network goes quiet and returns the devices found.
+///
+/// Starts a scan (or joins the one already running), then waits until no new service has been
+/// discovered for `quiet_secs` seconds or `max_secs` seconds have elapsed, whichever comes first.
+/// The scan is stopped before the accumulated devices are returned.
+#[command]
+pub async fn scan_until_quiet<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    quiet_secs: u64,
+    max_secs: u64,
+) -> Result<Vec<Device>, ScanError> {
+    start_scan(app.clone(), state.clone(), None, None, None, None).await?;
+    let Some(ctx) = state.scan.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+
+    let quiet = Duration::from_secs(quiet_secs);
+    let deadline = Instant::now() + Duration::from_secs(max_secs);
+    let reason = loop {
+        let now = Instant::now();
+        let quiet_until = *ctx.last_activity.lock().await + quiet;
+        if now >= quiet_until {
+            break ScanEndReason::Quiet;
+        }
+        if now >= deadline {
+            break ScanEndReason::Timeout;
+        }
+        tokio::time::sleep(quiet_until.min(deadline) - now).await;
+        if !*state.scanning.lock().await {
+            log::info!("Scan stopped before the network went quiet.");
+            break ScanEndReason::None;
+        }
+    };
+
+    if reason != ScanEndReason::None {
+        stop_scan_with_reason(&app, &state, reason).await?;
+    }
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard.values().cloned().collect())
+}
+
+/// Scans for `duration_secs` seconds, or the minimum scan duration if longer, and returns the
+/// devices found.
+///
+/// Only `service_types` are browsed when given, instead of the built-in ones; the rest of the
+/// plugin's default configuration applies. Events are emitted as for any other scan. Fails if a
+/// scan is already running, since it couldn't honor the duration or service types.
+#[command]
+pub async fn scan_once<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    duration_secs: u64,
+    service_types: Option<Vec<String>>,
+) -> Result<Vec<Device>, ScanError> {
+    let mut config = state.default_config.clone();
+    config.continuous = false;
+    if let Some(service_types) = service_types {
+        config.service_types = service_types;
+        config.replace_service_types = true;
+    }
+    start_new_scan(
+        app.clone(),
+        &state,
+        Some(config),
+        Some(duration_secs),
+        None,
+        None,
+        None,
+    )
+    .await?
+    .ok_or(ScanError::AlreadyScanning)?;
+
+    let duration_secs = state.effective_duration_secs(duration_secs);
+    tokio::time::sleep(Duration::from_secs(duration_secs)).await;
+    if *state.scanning.lock().await {
+        stop_scan_with_reason(&app, &state, ScanEndReason::Timeout).await?;
+    }
+    let devices_guard = state.devices.lock().await;
+    Ok(devices_guard.values().cloned().collect())
+}
+
+/// Applies `update` to the device with the given IP and emits `device-updated` if it reports a
+/// change. Returns the device as it is after the update.
+async fn update_device<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &MdnsState,
+    ip: &str,
+    update: impl FnOnce(&mut Device) -> bool,
+) -> Result<Device, ScanError> {
+    let mut devices_guard = state.devices.lock().await;
+    let Some(device) = devices_guard.get_mut(ip) else {
+        return Err(ScanError::DeviceNotFound(ip.to_string()));
+    };
+    let changed = update(device);
+    let device_payload = device.clone();
+    drop(devices_guard);
+
+    if changed {
+        publish_device(app, "device-updated", &device_payload).await;
+    }
+    Ok(device_payload)
+}
+
+/// Attaches a user tag to the device with the given IP and returns the updated device.
+#[command]
+pub async fn add_device_tag<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    ip: String,
+    tag: String,
+) -> Result<Device, ScanError> {
+    update_device(&app, &state, &ip, |device| {
+        if device.tags.contains(&tag) {
+            return false;
+        }
+        device.tags.push(tag);
+        true
+    })
+    .await
+}
+
+/// Removes a user tag from the device with the given IP and returns the updated device.
+#[command]
+pub async fn remove_device_tag<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    ip: String,
+    tag: String,
+) -> Result<Device, ScanError> {
+    update_device(&app, &state, &ip, |device| {
+        let len = device.tags.len();
+        device.tags.retain(|t| t != &tag);
+        device.tags.len() != len
+    })
+    .await
+}
+
+/// Forces resolution of a service instance that was found but never resolved.
+///
+/// `mdns-sd` has no per-instance resolve, so this re-browses `service_type` on the active daemon,
+/// which makes it re-query the network and resolve the instances it finds, then waits up to
+/// 5 seconds for `fullname` to resolve. Every other event received meanwhile is processed as usual,
+/// and the new browse keeps feeding the scan afterwards. Returns `None` on timeout.
+#[command]
+pub async fn resolve_instance<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    fullname: String,
+    service_type: String,
+) -> Result<Option<Device>, ScanError> {
+    let Some(mdns) = state.daemon.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+    let Some(ctx) = state.scan.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+    resolve_on_scan(&app, &mdns, &ctx, &fullname, service_type, RESOLVE_TIMEOUT).await
+}
+
+/// Re-browses `service_type` on the scan's daemon and waits up to `timeout` for `fullname` to
+/// resolve, processing every other event as usual. The running browse is stopped first so its
+/// receiver ends, and the new browse keeps feeding the scan afterwards.
+async fn resolve_on_scan<R: Runtime>(
+    app: &AppHandle<R>,
+    mdns: &ServiceDaemon,
+    ctx: &ScanContext,
+    fullname: &str,
+    service_type: String,
+    timeout: Duration,
+) -> Result<Option<Device>, ScanError> {
+    log::info!("Resolving {} ({})", fullname, service_type);
+    let receiver = mdns
+        .stop_browse(&service_type)
+        .and_then(|()| mdns.browse(&service_type))
+        .map_err(|e| ScanError::BrowseFailed {
+            service_type: service_type.clone(),
+            reason: e.to_string(),
+        })?;
+
+    let resolved = tokio::time::timeout(timeout, async {
+        while let Ok(event) = receiver.recv_async().await {
+            if let ServiceEvent::ServiceResolved(info) = event {
+                let is_target = info.get_fullname().eq_ignore_ascii_case(fullname);
+                let device =
+                    handle_resolved_service(info, app, ctx, base_service_type(&service_type)).await;
+                if is_target {
+                    return device;
+                }
+            }
+        }
+        None
+    })
+    .await
+    .unwrap_or_else(|_| {
+        log::info!("Timed out resolving {}", fullname);
+        None
+    });
+
+    let task = ctx.runtime.spawn(process_service_receiver(
+        receiver,
+        app.clone(),
+        ctx.clone(),
+        service_type,
+    ));
+    track_receiver_task(app, task).await;
+
+    Ok(resolved)
+}
+
+/// Returns the service type of a service instance's fullname, e.g. `_raop._tcp.local.` for
+/// `Living Room._raop._tcp.local.`, or `None` if it has no `_tcp` or `_udp` service type.
+///
+/// Instance names may contain dots, so the service type is found from the protocol label.
+fn fullname_service_type(fullname: &str) -> Option<&str> {
+    let protocol = fullname
+        .find("._tcp.")
+        .or_else(|| fullname.find("._udp."))?;
+    let start = fullname[..protocol].rfind('.')? + 1;
+    Some(&fullname[start..])
+}
+
+/// Builds a device from a single service resolved outside of any scan, filtering its addresses
+/// and naming it as the default configuration would. Returns `None` if it has no usable address.
+fn device_from_resolved_service(
+    info: &mdns_sd::ResolvedService,
+    service_type: &str,
+    state: &MdnsState,
+    elapsed_ms: u128,
+) -> Option<Device> {
+    let config = &state.default_config;
+    let mut addresses: Vec<&ScopedIp> = info
+        .get_addresses()
+        .iter()
+        .filter(|addr| is_kept_address(&addr.to_ip_addr(), config))
+        .collect();
+    addresses.sort_by_key(|addr| address_preference(&addr.to_ip_addr()));
+    let addresses: Vec<String> = addresses.into_iter().map(address_string).collect();
+    let ip = addresses.first()?.clone();
+    let device_type = resolve_device_type(
+        service_type,
+        info.get_fullname(),
+        &state.classification_rules,
+    )?;
+
+    let mut name = info
+        .get_fullname()
+        .split('.')
+        .next()
+        .unwrap_or("")
+        .to_string();
+    // `Device::hostname` is kept as advertised so it stays resolvable; only the alias recorded in
+    // `hostnames` is cleaned.
+    let mut alias_hostname = info.get_hostname().to_string();
+    if state.clean_names {
+        name = clean_name(&name);
+        alias_hostname = clean_name(&alias_hostname);
+    }
+    let mut device = Device {
+        name,
+        ip: ip.clone(),
+        discovery_time_ms: elapsed_ms,
+        hostname: info.get_hostname().to_string(),
+        hostnames: Vec::new(),
+        addresses: Vec::new(),
+        all_ips: Vec::new(),
+        additional_ips: Vec::new(),
+        services: Vec::new(),
+        tags: Vec::new(),
+        fingerprint: String::new(),
+        online: true,
+        mac: None,
+        vendor: None,
+        txt_keys: Vec::new(),
+        last_seen_at: Instant::now(),
+    };
+    device.add_aliases(&alias_hostname, &addresses);
+    device.all_ips = alternate_ips(&ip, &device.addresses);
+    device.add_or_update_service(
+        base_service_type(service_type),
+        info.get_port(),
+        device_type,
+        elapsed_ms,
+        decode_txt_properties(info.get_properties(), info.get_fullname()),
+        service_display_name(service_type),
+    );
+    device.set_service_fullname(base_service_type(service_type), info.get_fullname());
+    device.add_txt_keys(info.get_properties().iter().map(|p| p.key()));
+    device.update_fingerprint();
+    Some(device)
+}
+
+/// Resolves a single service instance by its fullname, e.g. one pasted by the user or
+/// deep-linked, without browsing every service type.
+///
+/// With a scan running, this behaves like `resolve_instance` with the given timeout. Otherwise a
+/// temporary daemon browses the instance's service type until it resolves or `timeout_ms`
+/// elapses, then shuts down; the device is returned without being added to the device list.
+/// Returns `None` on timeout.
+#[command]
+pub async fn resolve_fullname<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    fullname: String,
+    timeout_ms: u64,
+) -> Result<Option<Device>, ScanError> {
+    let Some(service_type) = fullname_service_type(&fullname) else {
+        return Err(ScanError::InvalidArgument(format!(
+            "'{fullname}' is not a service instance fullname"
+        )));
+    };
+    let timeout = Duration::from_millis(timeout_ms);
+    let daemon = state.daemon.lock().await.clone();
+    let ctx = state.scan.lock().await.clone();
+    if let (Some(mdns), Some(ctx)) = (daemon, ctx) {
+        let service_type = service_type.to_string();
+        return resolve_on_scan(&app, &mdns, &ctx, &fullname, service_type, timeout).await;
+    }
+
+    log::info!("Resolving {} on a temporary daemon", fullname);
+    let mdns = ServiceDaemon::new().map_err(|e| ScanError::DaemonInit(e.to_string()))?;
+    let start_time = Instant::now();
+    let receiver = mdns
+        .browse(service_type)
+        .map_err(|e| ScanError::BrowseFailed {
+            service_type: service_type.to_string(),
+            reason: e.to_string(),
+        });
+    let resolved = match &receiver {
+        Ok(receiver) => tokio::time::timeout(timeout, async {
+            while let Ok(event) = receiver.recv_async().await {
+                if let ServiceEvent::ServiceResolved(info) = event {
+                    if info.get_fullname().eq_ignore_ascii_case(&fullname) {
+                        return Some(info);
+                    }
+                }
+            }
+            None
+        })
+        .await
+        .unwrap_or_else(|_| {
+            log::info!("Timed out resolving {}", fullname);
+            None
+        }),
+        Err(_) => None,
+    };
+    if let Err(e) = mdns.shutdown() {
+        log::warn!("Failed to shutdown temporary mDNS daemon: {}", e);
+    }
+    receiver?;
+    let elapsed_ms = start_time.elapsed().as_millis();
+    Ok(resolved
+        .and_then(|info| device_from_resolved_service(&info, service_type, &state, elapsed_ms)))
+}
+
+/// Normalizes a service type answered to the DNS-SD meta-query, e.g. `_HTTP._tcp.local.` to
+/// `_http._tcp.local.`. Returns `None` for names that aren't a bare `_service._proto.local.`.
+fn advertised_service_type(name: &str) -> Option<String> {
+    let service_type = name.to_ascii_lowercase();
+    let service = service_type
+        .strip_suffix("._tcp.local.")
+        .or_else(|| service_type.strip_suffix("._udp.local."))?;
+    let label = service.strip_prefix('_')?;
+    if label.is_empty() || label.contains('.') {
+        return None;
+    }
+    Some(service_type)
+}
+
+/// Lists the service types advertised on the network, sorted and deduplicated, so the user can
+/// pick which ones to scan for.
+///
+/// A temporary daemon, independent of any running scan, browses the DNS-SD meta-query
+/// `_services._dns-sd._udp.local.` for `duration_secs` seconds, then shuts down. Each type is
+/// reported as the target of a found pointer record, without resolving any instance.
+#[command]
+pub async fn discover_service_types(duration_secs: u64) -> Result<Vec<String>, ScanError> {
+    log::info!("Discovering service types on a temporary daemon");
+    let mdns = ServiceDaemon::new().map_err(|e| ScanError::DaemonInit(e.to_string()))?;
+    let receiver = mdns
+        .browse(SERVICE_TYPE_ENUMERATION)
+        .map_err(|e| ScanError::BrowseFailed {
+            service_type: SERVICE_TYPE_ENUMERATION.to_string(),
+            reason: e.to_string(),
+        });
+    let mut service_types = BTreeSet::new();
+    if let Ok(receiver) = &receiver {
+        let _ = tokio::time::timeout(Duration::from_secs(duration_secs), async {
+            while let Ok(event) = receiver.recv_async().await {
+                if let ServiceEvent::ServiceFound(_, name) = event {
+                    match advertised_service_type(&name) {
+                        Some(service_type) => {
+                            service_types.insert(service_type);
+                        }
+                        None => log::debug!("Ignoring advertised service type {}", name),
+                    }
+                }
+            }
+        })
+        .await;
+    }
+    if let Err(e) = mdns.shutdown() {
+        log::warn!("Failed to shutdown temporary mDNS daemon: {}", e);
+    }
+    receiver?;
+    Ok(service_types.into_iter().collect())
+}
+
+/// Returns a rough estimate, in bytes, of the memory held by the device cache and the active
+/// scan's `seen_services` set.
+///
+/// The heuristic sums each collection's allocated capacity (one control byte per hash table
+/// slot plus the slot size) and the capacities of the strings and vectors stored in it. Allocator
+/// overhead and the daemon's own cache are not counted.
+#[command]
+pub async fn get_memory_estimate(state: State<'_, MdnsState>) -> Result<usize, ScanError> {
+    let devices_bytes = {
+        let devices_guard = state.devices.lock().await;
+        devices_guard.capacity() * (1 + std::mem::size_of::<(String, Device)>())
+            + devices_guard
+                .iter()
+                .map(|(ip, device)| ip.capacity() + device.heap_size())
+                .sum::<usize>()
+    };
+
+    let seen_services_bytes = match state.scan.lock().await.as_ref() {
+        Some(ctx) => {
+            let seen_guard = ctx.seen_services.lock().await;
+            seen_guard.capacity() * (1 + std::mem::size_of::<String>())
+                + seen_guard.iter().map(String::capacity).sum::<usize>()
+        }
+        None => 0,
+    };
+
+    Ok(devices_bytes + seen_services_bytes)
+}
+
+/// Returns why the last scan ended, or `none` if no scan has ended yet.
+#[command]
+pub async fn get_last_scan_reason(state: State<'_, MdnsState>) -> Result<ScanEndReason, ScanError> {
+    Ok(state.last_scan_reason.lock().await.clone())
+}
+
+/// Retrieves the summary of the last scan that ran, or `None` if no scan has run yet.
+#[command]
+pub async fn get_last_scan_report(
+    state: State<'_, MdnsState>,
+) -> Result<Option<ScanReport>, ScanError> {
+    Ok(state.last_scan_report.lock().await.clone())
+}
+
+/// Derives a host name for an advertised service from its instance name, keeping only ASCII
+/// letters and digits and replacing everything else with hyphens.
+fn registration_host_name(instance_name: &str) -> String {
+    let label: String = instance_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}.local.", label.trim_matches('-'))
+}
+
+/// Advertises a service of this host on the running scan's daemon, so companion devices can
+/// discover it, and returns its fullname for `unregister_service`.
+///
+/// The service is announced on every address of the host, and withdrawn when the scan stops or
+/// when the plugin is dropped. Pausing the scan withdraws it until the scan resumes.
+#[command]
+pub async fn register_service(
+    state: State<'_, MdnsState>,
+    service_type: String,
+    instance_name: String,
+    port: u16,
+    txt: HashMap<String, String>,
+) -> Result<String, ScanError> {
+    let Some(mdns) = state.daemon.lock().await.clone() else {
+        return Err(ScanError::NotScanning);
+    };
+    let info = ServiceInfo::new(
+        &service_type,
+        &instance_name,
+        &registration_host_name(&instance_name),
+        "",
+        port,
+        txt,
+    )
+    .map_err(|e| ScanError::InvalidArgument(e.to_string()))?
+    .enable_addr_auto();
+    let fullname = info.get_fullname().to_string();
+
+    log::info!("Registering {} on port {}", fullname, port);
+    mdns.register(info.clone())
+        .map_err(|e| ScanError::RegisterFailed(e.to_string()))?;
+    state
+        .registrations
+        .lock()
+        .await
+        .insert(fullname.clone(), info);
+    Ok(fullname)
+}
+
+/// Withdraws a service advertised with `register_service`, given the fullname it returned.
+///
+/// While the scan is paused the service is already withdrawn, and is only kept from being
+/// announced again on resume.
+#[command]
+pub async fn unregister_service(
+    state: State<'_, MdnsState>,
+    fullname: String,
+) -> Result<(), ScanError> {
+    if state.registrations.lock().await.remove(&fullname).is_none() {
+        return Err(ScanError::InvalidArgument(format!(
+            "No service registered as '{fullname}'"
+        )));
+    }
+    let Some(mdns) = state.daemon.lock().await.clone() else {
+        return Ok(());
+    };
+    log::info!("Unregistering {}", fullname);
+    mdns.unregister(&fullname)
+        .map_err(|e| ScanError::RegisterFailed(e.to_string()))?;
+    Ok(())
+}
+
+/// Retrieves the counters accumulated across the scans since the app started.
+#[command]
+pub async fn get_scan_stats(state: State<'_, MdnsState>) -> Result<ScanStats, ScanError> {
+    let mut stats = state.stats.lock().await.clone();
+    if let Some(ctx) = state.scan.lock().await.as_ref() {
+        stats.total_scan_time_ms += ctx.start_time.elapsed().as_millis();
+    }
+    Ok(stats)
+}
+
+/// Aggregates `discovery_time_ms` across the known devices, or returns `None` if there are none.
+#[command]
+pub async fn get_discovery_latency_stats(
+    state: State<'_, MdnsState>,
+) -> Result<Option<DiscoveryLatencyStats>, ScanError> {
+    Ok(DiscoveryLatencyStats::new(
+        state.devices.lock().await.values(),
+    ))
+}
+
+/// Streams a single device's updates down a channel.
+///
+/// The device is sent immediately if it is already known, then again on every subsequent update
+/// until the channel is dropped or the scan stops.
+#[command]
+pub async fn watch_device(
+    state: State<'_, MdnsState>,
+    ip: String,
+    channel: Channel<Device>,
+) -> Result<(), ScanError> {
+    if let Some(device) = state.devices.lock().await.get(&ip).cloned() {
+        if let Err(e) = channel.send(device) {
+            return Err(ScanError::Channel(e.to_string()));
+        }
+    }
+    state
+        .watchers
+        .lock()
+        .await
+        .entry(ip)
+        .or_default()
+        .push(channel);
+    Ok(())
+}
+
+/// Re-runs classification over every cached service and emits `device-updated` for each device
+/// whose types changed. Returns the changed devices.
+///
+/// Each service is classified from its stored `fullname`, the one it was last resolved under, so
+/// a cleaned device name doesn't change the result. Services whose type no longer resolves keep
+/// their previous classification.
+#[command]
+pub async fn reclassify_all<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+) -> Result<Vec<Device>, ScanError> {
+    let mut devices_guard = state.devices.lock().await;
+    let mut changed_devices = Vec::new();
+    for device in devices_guard.values_mut() {
+        if reclassify_services(device, &state.classification_rules) {
+            changed_devices.push(device.clone());
+        }
+    }
+    drop(devices_guard);
+
+    for device in &changed_devices {
+        publish_device(&app, "device-updated", device).await;
+    }
+    Ok(changed_devices)
+}
+
+/// Re-runs classification over the device's services from the fullname each was resolved
+/// under, since the device name may have been cleaned. Returns whether any type changed.
+fn reclassify_services(device: &mut Device, rules: &[(String, String)]) -> bool {
+    let mut changed = false;
+    for service in &mut device.services {
+        if let Some(device_type) =
+            resolve_device_type(&service.service_type, &service.fullname, rules)
+        {
+            if device_type != service.device_type {
+                service.device_type = device_type;
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Prunes the online devices no service was resolved on for `ttl_ms` milliseconds, or 2 minutes
+/// by default, the way a device losing its last service is handled: with `keep_offline_devices`
+/// they are marked offline and `device-updated` is emitted, otherwise they are removed along with
+/// their raw records and `device-removed` is emitted without a service type. `type-counts` follows
+/// if any device was pruned. Returns how many devices were pruned.
+///
+/// `keep_offline_devices` is taken from the active scan, or from the default configuration when
+/// none is running. The pruned devices' services are forgotten by the active scan, so they are
+/// picked up again if they reappear.
+#[command]
+pub async fn prune_stale_devices<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, MdnsState>,
+    ttl_ms: Option<u64>,
+) -> Result<usize, ScanError> {
+    let ttl = ttl_ms.map_or(DEFAULT_STALE_TTL, Duration::from_millis);
+    let stale: Vec<(String, String)> = state
+        .devices
+        .lock()
+        .await
+        .values()
+        .filter(|device| device.online && device.last_seen_at.elapsed() >= ttl)
+        .map(|device| (device.ip.clone(), device.name.clone()))
+        .collect();
+    if stale.is_empty() {
+        return Ok(0);
+    }
+
+    let scan = state.scan.lock().await.clone();
+    let keep_offline = scan
+        .as_ref()
+        .map_or(state.default_config.keep_offline_devices, |ctx| {
+            ctx.config.keep_offline_devices
+        });
+    if let Some(ctx) = &scan {
+        ctx.seen_services.lock().await.retain(|key| {
+            let ip = key.split_once('|').map_or(key.as_str(), |(ip, _)| ip);
+            !stale.iter().any(|(stale_ip, _)| stale_ip == ip)
+        });
+        ctx.instances
+            .lock()
+            .await
+            .retain(|_, ip| !stale.iter().any(|(stale_ip, _)| stale_ip == ip));
+    }
+
+    for (ip, name) in &stale {
+        log::info!("Pruned stale device {} ({})", name, ip);
+        retire_device(&app, ip, None, keep_offline).await;
+    }
+    let type_counts = count_devices_per_type(state.devices.lock().await.values());
+    emit_type_counts(&app, type_counts);
+    Ok(stale.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        advertised_service_type, alternate_ips, base_service_type, clean_name,
+        count_devices_per_type, decode_txt_properties, device_matches, emit_delay,
+        fullname_service_type, has_service_type, infer_daemon_error_cause, is_kept_address,
+        is_stale, is_usable_address, name_conflicts, parse_subnets, reclassify_services,
+        registration_host_name, resolve_device_type, scan_service_types, service_display_name,
+        sort_devices, Countdown, Subnet, BLUESOUND_SERVICE_TYPE, BUILTIN_SERVICE_TYPES,
+        CHROMECAST_SERVICE_TYPE, ROON_SERVICE_TYPE, VOLUMIO_SERVICE_TYPE,
+    };
+    use crate::models::{
+        DaemonErrorCause, Device, DeviceFilter, DeviceSort, DeviceType, DiscoveryLatencyStats,
+        IpVersion, ScanConfig,
+    };
+    use mdns_sd::{IntoTxtProperties, TxtProperty};
+    use std::collections::HashMap;
+    use std::net::IpAddr;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn clean_name_strips_trailing_dot() {
+        assert_eq!(clean_name("Living Room."), "Living Room");
+        assert_eq!(clean_name("Living Room.."), "Living Room");
+    }
+
+    #[test]
+    fn clean_name_strips_local_suffix() {
+        assert_eq!(clean_name("living-room.local."), "living-room");
+        assert_eq!(clean_name("living-room.local"), "living-room");
+        assert_eq!(clean_name("Living-Room.LOCAL."), "Living-Room");
+    }
+
+    #[test]
+    fn clean_name_keeps_other_names() {
+        assert_eq!(clean_name("Living Room"), "Living Room");
+        assert_eq!(clean_name("speaker.lan."), "speaker.lan");
+        assert_eq!(clean_name("mylocal"), "mylocal");
+    }
+
+    #[test]
+    fn clean_name_does_not_empty_bare_suffix() {
+        assert_eq!(clean_name(".local."), ".local");
+        assert_eq!(clean_name(""), "");
+        assert_eq!(clean_name("."), "");
+    }
+
+    #[test]
+    fn scan_service_types_adds_to_builtins() {
+        let config = ScanConfig {
+            service_types: vec![
+                "_sonos._tcp.local.".to_string(),
+                BUILTIN_SERVICE_TYPES[0].to_string(),
+            ],
+            ..Default::default()
+        };
+        let service_types = scan_service_types(&config).unwrap();
+        assert_eq!(service_types.len(), BUILTIN_SERVICE_TYPES.len() + 1);
+        assert_eq!(service_types.last().unwrap(), "_sonos._tcp.local.");
+    }
+
+    #[test]
+    fn scan_service_types_replaces_builtins() {
+        let config = ScanConfig {
+            service_types: vec!["_raop._tcp.local.".to_string()],
+            replace_service_types: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            scan_service_types(&config).unwrap(),
+            vec!["_raop._tcp.local.".to_string()]
+        );
+    }
+
+    #[test]
+    fn scan_service_types_rejects_invalid_types() {
+        let config = ScanConfig {
+            service_types: vec!["_raop._tcp".to_string()],
+            ..Default::default()
+        };
+        let err = scan_service_types(&config).unwrap_err();
+        assert!(err.to_string().contains("'_raop._tcp'"), "{err}");
+
+        let config = ScanConfig {
+            replace_service_types: true,
+            ..Default::default()
+        };
+        assert!(scan_service_types(&config).is_err());
+    }
+
+    #[test]
+    fn is_usable_address_filters_ipv6() {
+        let global: IpAddr = "2001:db8::1".parse().unwrap();
+        let unique_local: IpAddr = "fd12:3456::1".parse().unwrap();
+        assert!(!is_usable_address(&global, false, false));
+        assert!(is_usable_address(&global, true, false));
+        assert!(is_usable_address(&unique_local, true, false));
+        for addr in ["fe80::1", "febf::1", "::1", "::", "ff02::fb"] {
+            let addr: IpAddr = addr.parse().unwrap();
+            assert!(!is_usable_address(&addr, true, false), "{addr}");
+        }
+    }
+
+    #[test]
+    fn is_kept_address_filters_ip_version() {
+        let v4: IpAddr = "192.168.1.10".parse().unwrap();
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        let mut config = ScanConfig::default();
+        assert!(is_kept_address(&v4, &config));
+        assert!(!is_kept_address(&v6, &config));
+
+        config.ip_version = IpVersion::V4;
+        config.include_ipv6 = true;
+        assert!(is_kept_address(&v4, &config));
+        assert!(!is_kept_address(&v6, &config));
+
+        config.ip_version = IpVersion::V6;
+        config.include_ipv6 = false;
+        assert!(!is_kept_address(&v4, &config));
+        assert!(is_kept_address(&v6, &config));
+    }
+
+    #[test]
+    fn is_usable_address_skips_ipv4_link_local() {
+        let private: IpAddr = "192.168.1.10".parse().unwrap();
+        let link_local: IpAddr = "169.254.1.10".parse().unwrap();
+        assert!(is_usable_address(&private, false, false));
+        assert!(!is_usable_address(&link_local, true, false));
+    }
+
+    #[test]
+    fn is_usable_address_keeps_link_local_when_allowed() {
+        let link_local_v4: IpAddr = "169.254.1.10".parse().unwrap();
+        let link_local_v6: IpAddr = "fe80::1".parse().unwrap();
+        let loopback: IpAddr = "::1".parse().unwrap();
+        assert!(is_usable_address(&link_local_v4, false, true));
+        assert!(is_usable_address(&link_local_v6, false, true));
+        assert!(!is_usable_address(&loopback, true, true));
+    }
+
+    #[test]
+    fn subnet_contains_addresses_within_prefix() {
+        let subnet = Subnet::parse("192.168.1.0/24").unwrap();
+        assert!(subnet.contains(&"192.168.1.200".parse().unwrap()));
+        assert!(!subnet.contains(&"192.168.2.1".parse().unwrap()));
+        assert!(!subnet.contains(&"fd00::1".parse().unwrap()));
+
+        let host = Subnet::parse("10.0.0.5").unwrap();
+        assert!(host.contains(&"10.0.0.5".parse().unwrap()));
+        assert!(!host.contains(&"10.0.0.6".parse().unwrap()));
+
+        let everything = Subnet::parse("0.0.0.0/0").unwrap();
+        assert!(everything.contains(&"203.0.113.1".parse().unwrap()));
+
+        let unique_local = Subnet::parse("fd00::/8").unwrap();
+        assert!(unique_local.contains(&"fd12:3456::1".parse().unwrap()));
+        assert!(!unique_local.contains(&"2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn device_matches_subnets_of_either_family() {
+        let mut device = device("Speaker", "192.168.1.20", 100);
+        device.addresses = vec!["192.168.1.20".to_string(), "fd00::20".to_string()];
+        let filter = DeviceFilter::default();
+        let matches = |subnet: &str| device_matches(&device, &filter, Subnet::parse(subnet));
+        assert!(matches("192.168.1.0/24"));
+        assert!(matches("fd00::/8"));
+        assert!(!matches("10.0.0.0/8"));
+        assert!(!matches("fe80::/10"));
+    }
+
+    #[test]
+    fn parse_subnets_lists_malformed_entries() {
+        let subnets = ["10.0.0.0/8", "10.0.0.0/33", "router", "::/129"].map(String::from);
+        let err = parse_subnets("exclude", &subnets).unwrap_err().to_string();
+        assert!(
+            err.ends_with("in exclude: 10.0.0.0/33, router, ::/129"),
+            "{err}"
+        );
+        assert_eq!(parse_subnets("exclude", &subnets[..1]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn emit_delay_spaces_out_events() {
+        let interval = Duration::from_millis(250);
+        let last = Instant::now();
+        assert_eq!(emit_delay(None, last, interval), None);
+        assert_eq!(
+            emit_delay(Some(last), last + Duration::from_millis(100), interval),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(emit_delay(Some(last), last + interval, interval), None);
+        assert_eq!(emit_delay(Some(last), last, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn decode_txt_properties_skips_non_utf8_values() {
+        let properties = vec![
+            TxtProperty::from(("model", "Volumio 3.5")),
+            TxtProperty::from(("blob", vec![0xffu8, 0xfe])),
+            TxtProperty::from("flag"),
+        ]
+        .into_txt_properties();
+        let decoded = decode_txt_properties(&properties, "Living Room._http._tcp.local.");
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded["model"], "Volumio 3.5");
+        assert_eq!(decoded["flag"], "");
+    }
+
+    #[test]
+    fn service_display_name_labels_known_types() {
+        assert_eq!(service_display_name(BLUESOUND_SERVICE_TYPE), "Bluesound");
+        assert_eq!(service_display_name("_http._tcp.local."), "Web Service");
+        assert_eq!(
+            service_display_name("_spotify-connect._tcp.local."),
+            "Spotify Connect"
+        );
+        assert_eq!(
+            service_display_name("_qobuz-connect._tcp.local."),
+            "Qobuz Connect"
+        );
+        assert_eq!(
+            service_display_name("_speaker._sub._spotify-connect._tcp.local."),
+            "Spotify Connect"
+        );
+        assert_eq!(
+            service_display_name("_mybrand._tcp.local."),
+            "_mybrand._tcp.local."
+        );
+    }
+
+    #[test]
+    fn resolve_device_type_applies_rules_to_unrecognized_types() {
+        let rules = vec![
+            ("_mybrand.".to_string(), "My Brand".to_string()),
+            ("_tcp".to_string(), "Fallback".to_string()),
+        ];
+        assert_eq!(
+            resolve_device_type("_mybrand._tcp.local.", "Hub._mybrand._tcp.local.", &rules),
+            Some(DeviceType::Custom("My Brand".to_string()))
+        );
+        assert_eq!(
+            resolve_device_type("_other._udp.local.", "Hub._other._udp.local.", &rules),
+            Some(DeviceType::Generic)
+        );
+        assert_eq!(
+            resolve_device_type(BLUESOUND_SERVICE_TYPE, "Node._musc._tcp.local.", &rules),
+            Some(DeviceType::Bluesound)
+        );
+    }
+
+    #[test]
+    fn resolve_device_type_maps_roon() {
+        assert_eq!(
+            resolve_device_type(ROON_SERVICE_TYPE, "Study._raat._tcp.local.", &[]),
+            Some(DeviceType::Roon)
+        );
+        assert!(BUILTIN_SERVICE_TYPES.contains(&ROON_SERVICE_TYPE));
+    }
+
+    #[test]
+    fn resolve_device_type_maps_chromecast() {
+        assert_eq!(
+            resolve_device_type(
+                CHROMECAST_SERVICE_TYPE,
+                "Chromecast-Ultra-1a2b._googlecast._tcp.local.",
+                &[],
+            ),
+            Some(DeviceType::Chromecast)
+        );
+        assert!(BUILTIN_SERVICE_TYPES.contains(&CHROMECAST_SERVICE_TYPE));
+    }
+
+    #[test]
+    fn base_service_type_strips_subtypes() {
+        assert_eq!(
+            base_service_type("_printer._sub._http._tcp.local."),
+            "_http._tcp.local."
+        );
+        assert_eq!(
+            base_service_type("_sub._printer._tcp.local."),
+            "_printer._tcp.local."
+        );
+        assert_eq!(base_service_type("_http._tcp.local."), "_http._tcp.local.");
+        assert_eq!(
+            resolve_device_type(
+                "_speaker._sub._spotify-connect._tcp.local.",
+                "Kitchen._spotify-connect._tcp.local.",
+                &[],
+            ),
+            Some(DeviceType::SpotifyConnect)
+        );
+    }
+
+    #[test]
+    fn infer_daemon_error_cause_recognizes_common_errors() {
+        assert_eq!(
+            infer_daemon_error_cause("bind failed: Address already in use (os error 98)"),
+            DaemonErrorCause::PortInUse
+        );
+        assert_eq!(
+            infer_daemon_error_cause("Permission denied (os error 13)"),
+            DaemonErrorCause::PermissionDenied
+        );
+        assert_eq!(
+            infer_daemon_error_cause(
+                "An attempt was made to access a socket in a way forbidden by its access permissions. (os error 10013)"
+            ),
+            DaemonErrorCause::PermissionDenied
+        );
+        assert_eq!(
+            infer_daemon_error_cause("no interfaces"),
+            DaemonErrorCause::Unknown
+        );
+    }
+
+    fn device(name: &str, ip: &str, discovery_time_ms: u128) -> Device {
+        Device {
+            name: name.to_string(),
+            ip: ip.to_string(),
+            discovery_time_ms,
+            hostname: String::new(),
+            hostnames: Vec::new(),
+            addresses: Vec::new(),
+            all_ips: Vec::new(),
+            additional_ips: Vec::new(),
+            services: Vec::new(),
+            tags: Vec::new(),
+            fingerprint: String::new(),
+            online: true,
+            mac: None,
+            vendor: None,
+            txt_keys: Vec::new(),
+            last_seen_at: std::time::Instant::now(),
+        }
+    }
+
+    #[test]
+    fn is_stale_once_threshold_passes() {
+        assert!(!is_stale(1_000, 3_000, 5_000));
+        assert!(!is_stale(1_000, 6_000, 5_000));
+        assert!(is_stale(1_000, 6_001, 5_000));
+        // A device resolved after the elapsed time was sampled isn't stale.
+        assert!(!is_stale(7_000, 6_000, 5_000));
+    }
+
+    #[test]
+    fn sort_devices_orders_deterministically() {
+        let mut devices = vec![
+            device("kitchen", "192.168.1.10", 300),
+            device("Bedroom", "192.168.1.9", 300),
+            device("attic", "192.168.1.100", 100),
+        ];
+        let ips = |devices: &[Device]| devices.iter().map(|d| d.ip.clone()).collect::<Vec<_>>();
+
+        sort_devices(&mut devices, DeviceSort::DiscoveryTime);
+        assert_eq!(
+            ips(&devices),
+            ["192.168.1.100", "192.168.1.9", "192.168.1.10"]
+        );
+        sort_devices(&mut devices, DeviceSort::Name);
+        assert_eq!(
+            ips(&devices),
+            ["192.168.1.100", "192.168.1.9", "192.168.1.10"]
+        );
+        sort_devices(&mut devices, DeviceSort::Ip);
+        assert_eq!(
+            ips(&devices),
+            ["192.168.1.9", "192.168.1.10", "192.168.1.100"]
+        );
+    }
+
+    #[test]
+    fn registration_host_name_sanitizes_instance_names() {
+        assert_eq!(
+            registration_host_name("My App (Desk)"),
+            "My-App--Desk.local."
+        );
+        assert_eq!(registration_host_name("studio"), "studio.local.");
+    }
+
+    #[test]
+    fn name_conflicts_groups_shared_names() {
+        let devices = [
+            device("Kitchen", "192.168.1.10", 0),
+            device("Office", "192.168.1.11", 0),
+            device("Kitchen", "192.168.1.9", 0),
+        ];
+        assert_eq!(
+            name_conflicts(&devices),
+            vec![(
+                "Kitchen".to_string(),
+                vec!["192.168.1.9".to_string(), "192.168.1.10".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn count_devices_per_type_counts_each_device_once() {
+        let mut speaker = device("Kitchen", "192.168.1.10", 0);
+        speaker.add_or_update_service(
+            "_spotify-connect._tcp.local.",
+            4070,
+            DeviceType::SpotifyConnect,
+            0,
+            HashMap::new(),
+            service_display_name("_spotify-connect._tcp.local."),
+        );
+        speaker.add_or_update_service(
+            "_http._tcp.local.",
+            80,
+            DeviceType::Generic,
+            0,
+            HashMap::new(),
+            service_display_name("_http._tcp.local."),
+        );
+        speaker.add_or_update_service(
+            "_other._tcp.local.",
+            81,
+            DeviceType::Generic,
+            0,
+            HashMap::new(),
+            service_display_name("_other._tcp.local."),
+        );
+        let mut server = device("NAS", "192.168.1.11", 0);
+        server.add_or_update_service(
+            "_http._tcp.local.",
+            80,
+            DeviceType::Generic,
+            0,
+            HashMap::new(),
+            service_display_name("_http._tcp.local."),
+        );
+
+        let counts = count_devices_per_type([&speaker, &server]);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["spotifyConnect"], 1);
+        assert_eq!(counts["generic"], 2);
+    }
+
+    #[test]
+    fn discovery_latency_stats_use_nearest_rank_percentiles() {
+        assert_eq!(DiscoveryLatencyStats::new(&[]), None);
+
+        let devices: Vec<Device> = (1..=20)
+            .map(|i| device("Speaker", &format!("192.168.1.{}", i), i * 100))
+            .collect();
+        let stats = DiscoveryLatencyStats::new(&devices).unwrap();
+        assert_eq!(stats.device_count, 20);
+        assert_eq!(stats.min_ms, 100);
+        assert_eq!(stats.max_ms, 2000);
+        assert_eq!(stats.mean_ms, 1050.0);
+        assert_eq!(stats.p50_ms, 1000);
+        assert_eq!(stats.p95_ms, 1900);
+
+        let single = DiscoveryLatencyStats::new(&devices[..1]).unwrap();
+        assert_eq!((single.p50_ms, single.p95_ms), (100, 100));
+    }
+
+    #[test]
+    fn device_type_serializes_as_tagged_union() {
+        let cases = [
+            (DeviceType::Bluesound, r#"{"kind":"bluesound"}"#),
+            (DeviceType::Volumio, r#"{"kind":"volumio"}"#),
+            (DeviceType::SpotifyConnect, r#"{"kind":"spotifyConnect"}"#),
+            (DeviceType::QobuzConnect, r#"{"kind":"qobuzConnect"}"#),
+            (DeviceType::Roon, r#"{"kind":"roon"}"#),
+            (DeviceType::Chromecast, r#"{"kind":"chromecast"}"#),
+            (DeviceType::Generic, r#"{"kind":"generic"}"#),
+            (
+                DeviceType::Custom("myBrand".to_string()),
+                r#"{"kind":"custom","value":"myBrand"}"#,
+            ),
+        ];
+        for (device_type, json) in cases {
+            assert_eq!(serde_json::to_string(&device_type).unwrap(), json);
+            assert_eq!(
+                serde_json::from_str::<DeviceType>(json).unwrap(),
+                device_type
+            );
+        }
+    }
+
+    #[test]
+    fn has_service_type_matches_base_types() {
+        let mut player = device("Volumio", "192.168.1.10", 0);
+        player.add_or_update_service(
+            "_Volumio._tcp.local.",
+            3000,
+            DeviceType::Volumio,
+            0,
+            HashMap::new(),
+            service_display_name("_Volumio._tcp.local."),
+        );
+        let devices = [player];
+        assert!(has_service_type(&devices, "_volumio._tcp.local."));
+        assert!(has_service_type(
+            &devices,
+            "_player._sub._volumio._tcp.local."
+        ));
+        assert!(!has_service_type(&devices, "_http._tcp.local."));
+        assert!(!has_service_type(&[], "_volumio._tcp.local."));
+    }
+
+    #[test]
+    fn reclassify_services_uses_the_resolved_fullname() {
+        let mut player = device("Living Room", "192.168.1.10", 0);
+        player.add_or_update_service(
+            VOLUMIO_SERVICE_TYPE,
+            80,
+            DeviceType::Generic,
+            0,
+            HashMap::new(),
+            service_display_name(VOLUMIO_SERVICE_TYPE),
+        );
+        player.set_service_fullname(
+            VOLUMIO_SERVICE_TYPE,
+            "Volumio Living Room._http._tcp.local.",
+        );
+        assert!(reclassify_services(&mut player, &[]));
+        assert_eq!(player.services[0].device_type, DeviceType::Volumio);
+        assert!(!reclassify_services(&mut player, &[]));
+    }
+
+    #[test]
+    fn countdown_leaves_out_paused_time() {
+        let start = Instant::now();
+        let mut countdown = Countdown::new(start, Duration::from_secs(30));
+        assert_eq!(countdown.seconds_left_at(start), 30);
+        assert_eq!(
+            countdown.seconds_left_at(start + Duration::from_millis(9_500)),
+            21
+        );
+
+        countdown.pause(start + Duration::from_secs(10));
+        assert_eq!(
+            countdown.seconds_left_at(start + Duration::from_secs(25)),
+            20
+        );
+        countdown.resume(start + Duration::from_secs(25));
+        assert_eq!(
+            countdown.seconds_left_at(start + Duration::from_secs(30)),
+            15
+        );
+        assert_eq!(
+            countdown.seconds_left_at(start + Duration::from_secs(60)),
+            0
+        );
+    }
+
+    #[test]
+    fn alternate_ips_prefer_global_addresses() {
+        let addresses = [
+            "fe80::1%en0".to_string(),
+            "192.168.1.10".to_string(),
+            "2001:db8::1".to_string(),
+            "fd00::1".to_string(),
+            "169.254.3.4".to_string(),
+            "8.8.4.4".to_string(),
+        ];
+        assert_eq!(
+            alternate_ips("8.8.4.4", &addresses),
+            vec![
+                "2001:db8::1",
+                "192.168.1.10",
+                "fd00::1",
+                "169.254.3.4",
+                "fe80::1%en0"
+            ]
+        );
+    }
+
+    #[test]
+    fn advertised_service_type_keeps_bare_types() {
+        assert_eq!(
+            advertised_service_type("_HTTP._tcp.local.").as_deref(),
+            Some("_http._tcp.local.")
+        );
+        assert_eq!(
+            advertised_service_type("_sleep-proxy._udp.local.").as_deref(),
+            Some("_sleep-proxy._udp.local.")
+        );
+        assert_eq!(
+            advertised_service_type("_printer._sub._ipp._tcp.local."),
+            None
+        );
+        assert_eq!(
+            advertised_service_type("Living Room._raop._tcp.local."),
+            None
+        );
+        assert_eq!(advertised_service_type("_._tcp.local."), None);
+        assert_eq!(advertised_service_type("_http._tcp.example.com."), None);
+    }
+
+    #[test]
+    fn fullname_service_type_finds_the_protocol_label() {
+        assert_eq!(
+            fullname_service_type("Living Room._raop._tcp.local."),
+            Some("_raop._tcp.local.")
+        );
+        assert_eq!(
+            fullname_service_type("Dr. Who's Speaker._spotify-connect._tcp.local."),
+            Some("_spotify-connect._tcp.local.")
+        );
+        assert_eq!(
+            fullname_service_type("Clock._ntp._udp.local."),
+            Some("_ntp._udp.local.")
+        );
+        assert_eq!(fullname_service_type("volumio.local."), None);
+    }
 }
@@ -0,0 +1,84 @@
+//! The error type returned by the plugin's commands.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// An error returned by a command.
+///
+/// Serialized as `{ kind, message }`, where `kind` is the camelCase name of the variant for the
+/// frontend to branch on, and `message` describes the error for humans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError {
+    /// The mDNS daemon could not be created.
+    DaemonInit(String),
+    /// A service type could not be browsed.
+    BrowseFailed {
+        /// The service type that could not be browsed.
+        service_type: String,
+        /// Why browsing failed.
+        reason: String,
+    },
+    /// The command needs a running scan, but none is in progress.
+    NotScanning,
+    /// The command starts its own scan, but one is already in progress.
+    AlreadyScanning,
+    /// The mDNS daemon could not be shut down.
+    Shutdown(String),
+    /// An argument or the scan configuration is invalid.
+    InvalidArgument(String),
+    /// No device is known at the given IP.
+    DeviceNotFound(String),
+    /// A value could not be sent down a channel.
+    Channel(String),
+    /// A service could not be advertised or withdrawn.
+    RegisterFailed(String),
+}
+
+impl ScanError {
+    /// Returns the discriminant the error is serialized with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScanError::DaemonInit(_) => "daemonInit",
+            ScanError::BrowseFailed { .. } => "browseFailed",
+            ScanError::NotScanning => "notScanning",
+            ScanError::AlreadyScanning => "alreadyScanning",
+            ScanError::Shutdown(_) => "shutdown",
+            ScanError::InvalidArgument(_) => "invalidArgument",
+            ScanError::DeviceNotFound(_) => "deviceNotFound",
+            ScanError::Channel(_) => "channel",
+            ScanError::RegisterFailed(_) => "registerFailed",
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::DaemonInit(reason) => write!(f, "Failed to create mDNS daemon: {reason}"),
+            ScanError::BrowseFailed {
+                service_type,
+                reason,
+            } => write!(f, "Failed to browse for service '{service_type}': {reason}"),
+            ScanError::NotScanning => write!(f, "No scan is in progress"),
+            ScanError::AlreadyScanning => write!(f, "A scan is already in progress"),
+            ScanError::Shutdown(reason) => write!(f, "Failed to shutdown mDNS daemon: {reason}"),
+            ScanError::InvalidArgument(reason) => write!(f, "{reason}"),
+            ScanError::DeviceNotFound(ip) => write!(f, "No device found with IP {ip}"),
+            ScanError::Channel(reason) => write!(f, "Failed to send to channel: {reason}"),
+            ScanError::RegisterFailed(reason) => {
+                write!(f, "Failed to update service registration: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+impl Serialize for ScanError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("ScanError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
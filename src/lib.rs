@@ -4,30 +4,247 @@
     windows_subsystem = "windows"
 )]
 
+use std::collections::HashMap;
+use std::time::Duration;
 use tauri::{
-    plugin::{Builder, TauriPlugin},
-    Manager, Runtime,
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    AppHandle, Manager, Runtime,
 };
+use tokio::sync::mpsc;
 
+mod arp;
 mod commands;
+mod error;
 mod models;
+mod oui;
+mod packed;
 
-/// Initializes the LAN scanner plugin.
+pub use models::{Device, DeviceType, DiscoveredService, IpVersion, ScanConfig, TimeoutAction};
+
+/// Configures and builds the LAN scanner plugin.
+#[derive(Default)]
+pub struct Builder {
+    worker_threads: Option<usize>,
+    clean_names: bool,
+    debounce_windows: HashMap<String, Duration>,
+    classification_rules: Vec<(String, String)>,
+    min_scan_duration_secs: Option<u64>,
+    event_prefix: Option<String>,
+    default_config: ScanConfig,
+}
+
+impl Builder {
+    /// Creates a builder with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the scan tasks on a dedicated multi-thread Tokio runtime with the given number of
+    /// worker threads, isolating discovery from the rest of the app's async work.
+    ///
+    /// Without this, scan tasks are spawned on the ambient runtime.
+    pub fn dedicated_runtime(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = Some(worker_threads.max(1));
+        self
+    }
+
+    /// Strips the trailing dot and the `.local` suffix from device names and the hostnames listed
+    /// in `hostnames` before they are stored, so `living-room.local.` is reported as
+    /// `living-room`. A device's `hostname` is kept as advertised, since a cleaned name would no
+    /// longer resolve.
+    pub fn clean_names(mut self, enabled: bool) -> Self {
+        self.clean_names = enabled;
+        self
+    }
+
+    /// Coalesces the `new-device` and `device-updated` events triggered by `service_type` within
+    /// `window` into a single event carrying the device's latest state. A device first found
+    /// within the window is still announced through `new-device`.
+    ///
+    /// Use this to quiet chatty service types. Types without a window emit immediately.
+    pub fn debounce(mut self, service_type: impl Into<String>, window: Duration) -> Self {
+        self.debounce_windows.insert(service_type.into(), window);
+        self
+    }
+
+    /// Labels services whose type contains `pattern` as `DeviceType::Custom(label)`, e.g.
+    /// `classify("_mybrand._tcp", "My Brand")`.
+    ///
+    /// Rules only apply to services the built-in classification doesn't recognize, and are tried
+    /// in the order they were added. Services matching no rule stay `Generic`.
+    pub fn classify(mut self, pattern: impl Into<String>, label: impl Into<String>) -> Self {
+        self.classification_rules
+            .push((pattern.into(), label.into()));
+        self
+    }
+
+    /// Sets the shortest duration, in seconds, a scan runs for. Shorter durations passed to
+    /// `start_scan` are raised to it with a warning, so a stray tiny value doesn't produce a scan
+    /// that barely finds anything.
+    ///
+    /// Defaults to 3 seconds. Pass 0 to honor any duration.
+    pub fn min_scan_duration_secs(mut self, secs: u64) -> Self {
+        self.min_scan_duration_secs = Some(secs);
+        self
+    }
+
+    /// Prefixes the name of every emitted event with `prefix` and a colon, so `new-device` is
+    /// emitted as `lan:new-device` for a prefix of `lan`, to avoid collisions with other plugins'
+    /// events. The frontend must then call `setEventPrefix` with the same prefix.
+    ///
+    /// Like event names, the prefix may only contain alphanumeric characters, `-`, `/`, `:` and
+    /// `_`. Events keep their bare names without it.
+    pub fn event_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.event_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the configuration `start_scan` uses when called without one, and that
+    /// `get_default_config` reports. Defaults to `ScanConfig::default()`.
+    pub fn default_config(mut self, config: ScanConfig) -> Self {
+        self.default_config = config;
+        self
+    }
+
+    /// Builds the plugin.
+    ///
+    /// This creates and configures the Tauri plugin, setting up the necessary state
+    /// and registering the invoke handlers for the frontend API.
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        PluginBuilder::new("lan-scanner")
+            .invoke_handler(tauri::generate_handler![
+                commands::start_scan,
+                commands::stop_scan,
+                commands::is_scanning,
+                commands::get_discovered_devices,
+                commands::get_devices_by_port,
+                commands::boost_scan,
+                commands::get_default_config,
+                commands::get_devices_packed,
+                commands::get_fastest_device_of_type,
+                commands::scan_until_quiet,
+                commands::add_device_tag,
+                commands::remove_device_tag,
+                commands::resolve_instance,
+                commands::get_memory_estimate,
+                commands::get_last_scan_reason,
+                commands::watch_device,
+                commands::reclassify_all,
+                commands::query_devices,
+                commands::get_device_by_fingerprint,
+                commands::devices_with_service_not_other,
+                commands::get_multiservice_devices,
+                commands::get_last_scan_report,
+                commands::prune_stale_devices,
+                commands::get_device_by_ip,
+                commands::rescan_device,
+                commands::scan_once,
+                commands::clear_devices,
+                commands::get_devices_by_type,
+                commands::get_scan_stats,
+                commands::pause_scan,
+                commands::resume_scan,
+                commands::is_paused,
+                commands::get_current_scan_config,
+                commands::register_service,
+                commands::unregister_service,
+                commands::get_name_conflicts,
+                commands::replay_devices,
+                commands::get_type_counts,
+                commands::get_discovery_latency_stats,
+                commands::has_device_of_service_type,
+                commands::get_scan_time_remaining,
+                commands::resolve_fullname,
+                commands::start_scan_with_channel,
+                commands::get_raw_records,
+                commands::force_shutdown,
+                commands::get_devices_with_freshness,
+                commands::discover_service_types
+            ])
+            .setup(move |app, _api| {
+                let runtime = match self.worker_threads {
+                    Some(worker_threads) => Some(spawn_dedicated_runtime(worker_threads)?),
+                    None => None,
+                };
+                log::info!("lan-scanner plugin initialized");
+                app.manage(commands::MdnsState {
+                    runtime,
+                    clean_names: self.clean_names,
+                    debounce_windows: self.debounce_windows,
+                    classification_rules: self.classification_rules,
+                    min_scan_duration_secs: self.min_scan_duration_secs,
+                    event_prefix: self.event_prefix,
+                    default_config: self.default_config,
+                    ..Default::default()
+                });
+                Ok(())
+            })
+            .on_drop(|app| {
+                if let Some(state) = app.try_state::<commands::MdnsState>() {
+                    state.teardown();
+                }
+            })
+            .build()
+    }
+}
+
+/// Starts a multi-thread Tokio runtime on its own thread and returns a handle to it.
+///
+/// The runtime lives for the rest of the process, so it is never dropped from within an async
+/// context.
+fn spawn_dedicated_runtime(worker_threads: usize) -> std::io::Result<tokio::runtime::Handle> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(worker_threads)
+        .thread_name("lan-scanner-worker")
+        .enable_all()
+        .build()?;
+    let handle = runtime.handle().clone();
+    std::thread::Builder::new()
+        .name("lan-scanner-runtime".into())
+        .spawn(move || runtime.block_on(std::future::pending::<()>()))?;
+    Ok(handle)
+}
+
+/// Initializes the LAN scanner plugin with the default settings.
 ///
-/// This function creates and configures the Tauri plugin, setting up the necessary state
-/// and registering the invoke handlers for the frontend API.
+/// Use [`Builder`] to customize the plugin.
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("lan-scanner")
-        .invoke_handler(tauri::generate_handler![
-            commands::start_scan,
-            commands::stop_scan,
-            commands::is_scanning,
-            commands::get_discovered_devices
-        ])
-        .setup(|app, _api| {
-            log::info!("lan-scanner plugin initialized");
-            app.manage(commands::MdnsState::default());
-            Ok(())
-        })
-        .build()
+    Builder::new().build()
+}
+
+/// Initializes the LAN scanner plugin with the default settings, prefixing the name of every
+/// emitted event with `prefix`, as with [`Builder::event_prefix`].
+pub fn init_with_prefix<R: Runtime>(prefix: impl Into<String>) -> TauriPlugin<R> {
+    Builder::new().event_prefix(prefix).build()
+}
+
+/// Returns a snapshot of the discovered devices, for other Rust code such as a companion plugin
+/// that can't go through the frontend.
+///
+/// Returns an empty list if the plugin isn't registered on `app`.
+pub async fn current_devices<R: Runtime>(app: &AppHandle<R>) -> Vec<Device> {
+    let Some(state) = app.try_state::<commands::MdnsState>() else {
+        return Vec::new();
+    };
+    let devices_guard = state.devices.lock().await;
+    devices_guard.values().cloned().collect()
+}
+
+/// Subscribes to the devices the plugin publishes, for Rust code such as a headless test harness
+/// that can't listen to events.
+///
+/// The receiver gets the device's latest state whenever `new-device` or `device-updated` fires.
+/// It buffers up to 64 devices; updates arriving while it's full are dropped, so keep receiving.
+/// Dropping the receiver unsubscribes. If the plugin isn't registered on `app`, the receiver is
+/// closed right away.
+pub fn subscribe_devices<R: Runtime>(app: &AppHandle<R>) -> mpsc::Receiver<Device> {
+    let (sender, receiver) = mpsc::channel(commands::SUBSCRIBER_CAPACITY);
+    if let Some(state) = app.try_state::<commands::MdnsState>() {
+        state
+            .subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(sender);
+    }
+    receiver
 }
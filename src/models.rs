@@ -1,4 +1,510 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+/// Options controlling how a LAN scan behaves.
+///
+/// Every field is optional on the frontend; missing fields fall back to their defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanConfig {
+    /// Whether to keep services that resolve with port 0. Such services are never connectable,
+    /// so they are skipped by default.
+    pub keep_zero_port: bool,
+    /// Whether to merge services whose hostnames resolve to overlapping address sets into a single
+    /// device, even when they are advertised under different hostnames (aliases).
+    pub collapse_aliases: bool,
+    /// Whether to recognize a known device that reappears at a new IP (e.g. after a DHCP lease
+    /// renewal) by its hostname or instance name, and move it instead of adding a duplicate.
+    pub track_ip_changes: bool,
+    /// The maximum number of devices collected per service type. Once a service type reaches it,
+    /// new devices exposing that type are ignored; known devices still pick it up. Unbounded when `None`.
+    pub per_type_limit: Option<usize>,
+    /// The maximum number of devices, counted by unique IP. Once it is reached, new devices are
+    /// ignored while known ones keep updating, and the scan ends with `ScanEndReason::Count`
+    /// instead of `ScanEndReason::Timeout`. Unbounded when `None`.
+    pub max_devices: Option<usize>,
+    /// Whether to re-browse every service type on an exponential back-off (1s, 2s, 4s, ...) for
+    /// the whole scan, on top of the daemon's own re-queries. This sends more traffic but catches
+    /// devices that only announce periodically.
+    pub reliable: bool,
+    /// A prefix instance names must start with, e.g. `NODE-`. Services of other instances are
+    /// skipped before they become devices or fire events. Every instance is kept when `None`.
+    pub instance_prefix: Option<String>,
+    /// Whether a device that loses its last service is kept with `online` set to `false` instead
+    /// of being deleted, so it can be listed as a known device with live status.
+    pub keep_offline_devices: bool,
+    /// An upper bound, in seconds, on how long the scan may run, however it was started. Once it
+    /// elapses the scan stops with `ScanEndReason::MaxLifetime`. Unbounded when `None`.
+    pub max_lifetime_secs: Option<u64>,
+    /// Service types whose services are dropped when they classify as `DeviceType::Generic`.
+    /// They still count when they classify as something specific, e.g. Volumio by its name.
+    pub ignore_generic_service_types: Vec<String>,
+    /// Extra service types to browse, e.g. `_sonos._tcp.local.`. Each must end with `.local.`.
+    /// Services of types the plugin doesn't recognize are classified as `DeviceType::Generic`.
+    pub service_types: Vec<String>,
+    /// Whether `service_types` replaces the built-in service types instead of adding to them.
+    pub replace_service_types: bool,
+    /// Whether to keep global and unique-local IPv6 addresses alongside IPv4 ones. A dual-stack
+    /// device is keyed by its most routable address, which is its IPv4 one only among equally
+    /// routable addresses.
+    pub include_ipv6: bool,
+    /// Whether to keep link-local addresses (`169.254.0.0/16` and `fe80::/10`), e.g. on a closed
+    /// appliance network where they are the only ones advertised. Link-local IPv6 addresses are
+    /// kept even without `include_ipv6`, and stored with their zone, e.g. `fe80::1%en0`.
+    pub allow_link_local: bool,
+    /// Whether to keep the devices of previous scans and merge newly discovered services into
+    /// them, instead of clearing the device list when the scan starts. A kept device's
+    /// `discovery_time_ms` is then the lowest across the merged scans, not the current scan's.
+    pub preserve_previous: bool,
+    /// Whether the scan runs until `stop_scan` is called instead of stopping once its duration
+    /// elapses. No `scan-tick` events are emitted; `max_lifetime_secs` still applies.
+    pub continuous: bool,
+    /// How often, in seconds, a continuous scan emits `device-heartbeat` with the current device
+    /// and service counts, even when nothing changed. No heartbeat fires when `None`, nor for
+    /// scans with a duration, which emit `scan-tick` every second instead.
+    pub heartbeat_interval_secs: Option<u64>,
+    /// The network interfaces to scan on, by name (e.g. `en0`) or IP address. Every interface
+    /// is used when empty.
+    pub interfaces: Vec<String>,
+    /// Whether to merge a device into a known one with the same name at another IP, e.g. a
+    /// player with both wired and wireless interfaces. The other IP is listed in the known
+    /// device's `additional_ips`, and its `discovery_time_ms` is the lowest of the two.
+    pub merge_by_name: bool,
+    /// Whether to try a TCP connection to each newly resolved service and record whether it
+    /// succeeded in the service's `reachable`. The device is emitted through `device-updated`
+    /// once the check changes it.
+    pub verify_reachability: bool,
+    /// How long, in milliseconds, a reachability check waits for the connection. Defaults to
+    /// 500 when `None`.
+    pub reachability_timeout_ms: Option<u64>,
+    /// Whether to look up each IPv4 device's MAC address in the system's ARP table and record it
+    /// in the device's `mac`, along with the vendor its prefix is registered to in `vendor`. The
+    /// device is emitted through `device-updated` once it's found.
+    pub resolve_mac: bool,
+    /// How often, in milliseconds, to re-query every service type on top of the daemon's own
+    /// queries, for faster discovery on busy networks at the cost of more multicast traffic.
+    /// Each re-query restarts the type's browse, as the daemon's own query interval can't be set.
+    /// Values below 1000 are raised to 1000. Only the daemon's own queries are sent when `None`.
+    pub query_interval_ms: Option<u64>,
+    /// What the scan does with the mDNS daemon once its duration elapses.
+    pub on_timeout: TimeoutAction,
+    /// The IP version the daemon binds to and addresses are kept for.
+    pub ip_version: IpVersion,
+    /// Whether to process every resolution of a service, instead of only the first one per
+    /// device and service type, so a changed port or `last_seen_ms` is picked up mid-scan.
+    /// `new-device` still only fires when a device is first inserted; re-resolutions that change
+    /// it emit `device-updated`, and the others `device-seen`.
+    pub allow_reresolution: bool,
+    /// The shortest time, in milliseconds, between two `new-device` or `device-updated` events
+    /// for the same device. Updates arriving sooner are coalesced into one event carrying the
+    /// device's latest state, emitted once the interval has passed. Defaults to 250 when `None`,
+    /// and 0 emits every update.
+    pub device_emit_interval_ms: Option<u64>,
+    /// IP addresses and CIDR subnets, like `192.168.1.1` or `10.0.0.0/24`, whose devices are
+    /// ignored. A service is skipped if any of its addresses matches.
+    pub exclude: Vec<String>,
+    /// CIDR subnets, like `10.20.0.0/16`, outside of which addresses are ignored. A service with
+    /// no address left is skipped. Every address is kept when empty. Applied before `exclude`.
+    pub include_only: Vec<String>,
+}
+
+/// The IP versions a scan runs on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IpVersion {
+    /// IPv4 only. The daemon doesn't bind any IPv6 interface, and IPv6 addresses are dropped.
+    V4,
+    /// IPv6 only. The daemon doesn't bind any IPv4 interface, IPv4 addresses are dropped, and
+    /// IPv6 ones are kept as with `include_ipv6`.
+    V6,
+    /// Both versions. Which IPv6 addresses are kept is up to `include_ipv6`.
+    #[default]
+    Both,
+}
+
+/// What a scan does with the mDNS daemon once its duration elapses.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeoutAction {
+    /// Shut the daemon down, as when the scan is stopped.
+    #[default]
+    Shutdown,
+    /// Keep the daemon and the scan's context alive so follow-ups like `rescan_device` are
+    /// instant. The caller must eventually call `stop_scan` to free the daemon.
+    Idle,
+}
+
+/// The order `get_discovered_devices` returns devices in.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceSort {
+    /// By `discovery_time_ms`, earliest first.
+    #[default]
+    DiscoveryTime,
+    /// By name, case-insensitively.
+    Name,
+    /// By IP address, numerically, IPv4 before IPv6.
+    Ip,
+}
+
+/// Criteria for `query_devices`. Every provided criterion must match; a list criterion matches
+/// when any of its entries does.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeviceFilter {
+    /// Device types, at least one of which a service on the device must be classified as.
+    pub types: Option<Vec<DeviceType>>,
+    /// Service types, at least one of which the device must expose.
+    pub service_types: Option<Vec<String>>,
+    /// A case-insensitive substring of the device's name.
+    pub name_contains: Option<String>,
+    /// An IPv4 or IPv6 subnet in CIDR notation (e.g. `192.168.1.0/24` or `fd00::/8`) one of the
+    /// device's addresses must fall in. A bare address matches only itself.
+    pub subnet: Option<String>,
+    /// A port a service on the device must be advertised on.
+    pub port: Option<u16>,
+    /// The time in milliseconds from the start of the scan since which a service on the device
+    /// must have been seen.
+    pub seen_since_ms: Option<u64>,
+}
+
+/// Wraps every emitted event payload with its position in the scan's event stream.
+///
+/// The sequence number increases monotonically for each event and resets when a new scan starts,
+/// so the frontend can reorder or discard events that arrive out of order.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SequencedEvent<T> {
+    /// The sequence number of this event within the current scan.
+    pub seq: u64,
+    /// The id of the current or, between scans, the last scan, or `None` before the first one.
+    pub scan_id: Option<String>,
+    /// The event's payload.
+    pub payload: T,
+}
+
+/// The payload of the `device-ip-changed` event, emitted when a known device moves to a new IP.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceIpChange {
+    /// The IP address the device was previously known at.
+    pub old_ip: String,
+    /// The IP address the device is now known at.
+    pub new_ip: String,
+}
+
+/// A device returned by `get_devices_with_freshness`, flagged by whether it was seen recently.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceFreshness {
+    #[serde(flatten)]
+    pub device: Device,
+    /// Whether none of the device's services was seen within the requested threshold.
+    pub stale: bool,
+}
+
+/// The payload of the `device-heartbeat` event, emitted periodically during a continuous scan.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceHeartbeat {
+    /// How many devices are known.
+    pub device_count: usize,
+    /// How many services the known devices expose in total.
+    pub service_count: usize,
+}
+
+/// The payload of the `scan-started` event, emitted once a scan's daemon is up.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStarted {
+    /// How long the scan runs before it stops automatically, in seconds, after the minimum scan
+    /// duration is applied, or `None` for a continuous scan.
+    pub duration_secs: Option<u64>,
+    /// The service types being browsed.
+    pub service_types: Vec<String>,
+}
+
+/// The configuration `start_scan` uses when called without one, returned by `get_default_config`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultScanConfig {
+    #[serde(flatten)]
+    pub config: ScanConfig,
+    /// How long a scan started without a duration runs, in seconds, after the minimum scan
+    /// duration is applied, or `None` when the configuration is continuous.
+    pub duration_secs: Option<u64>,
+}
+
+/// The effective settings of the running scan, or of a fresh scan started without arguments when
+/// none is running, returned by `get_current_scan_config`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentScanConfig {
+    /// Whether a scan is running, so the settings are the active ones rather than the defaults.
+    pub active: bool,
+    /// How long the scan runs before it stops automatically, in seconds, or `None` for a
+    /// continuous scan.
+    pub duration_secs: Option<u64>,
+    /// The service types browsed.
+    pub service_types: Vec<String>,
+    /// Whether IPv6 addresses are kept.
+    pub include_ipv6: bool,
+    /// Whether the scan runs until it is stopped.
+    pub continuous: bool,
+}
+
+/// The payload of the `device-removed` event, emitted when a device leaves the device list.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceRemoved {
+    /// The IP address the device was known at.
+    pub ip: String,
+    /// The service type whose removal took the device's last service, or `None` if the device was
+    /// pruned as stale.
+    pub service_type: Option<String>,
+}
+
+/// The likely cause of a failure to create the mDNS daemon, inferred from its error.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DaemonErrorCause {
+    /// The mDNS port (UDP 5353) is already bound by another process without sharing.
+    PortInUse,
+    /// The OS or a firewall denied access to the mDNS port.
+    PermissionDenied,
+    /// The error didn't match any known cause.
+    Unknown,
+}
+
+/// The payload of the `daemon-error` event, emitted when the mDNS daemon can't be created.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DaemonError {
+    /// The error of the last attempt.
+    pub message: String,
+    /// The likely cause, for the UI to suggest a fix such as a firewall rule.
+    pub cause: DaemonErrorCause,
+    /// How many times creating the daemon was attempted.
+    pub attempts: u32,
+}
+
+/// The payload of the `browse-error` event, emitted when a scan can't browse for a service type.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowseError {
+    /// The service type that couldn't be browsed.
+    pub service_type: String,
+    /// The error the daemon reported.
+    pub message: String,
+    /// Whether browsing for at least one other service type of the scan succeeded.
+    pub any_succeeded: bool,
+}
+
+/// The payload of the `device-seen` event, emitted when a known device's service resolves again
+/// without changing.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSeen {
+    /// The IP address of the device.
+    pub ip: String,
+    /// The time in milliseconds from the start of the scan when the device was seen.
+    pub last_seen_ms: u128,
+}
+
+/// The raw fields of a resolved service instance, as last received, for diagnosing
+/// classification misses without a separate `dns-sd` or `avahi-browse` session.
+///
+/// The mDNS library doesn't expose the SRV record's priority and weight, so they aren't included.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawServiceRecord {
+    /// The instance's fullname, e.g. `Living Room._musc._tcp.local.`.
+    pub fullname: String,
+    /// The service type and domain, e.g. `_musc._tcp.local.`.
+    pub service_type: String,
+    /// The subtype the instance was found under, if any.
+    pub subtype: Option<String>,
+    /// The hostname from the SRV record, before any cleaning.
+    pub hostname: String,
+    /// The port from the SRV record.
+    pub port: u16,
+    /// Every advertised address, including the ones the scan configuration filters out.
+    pub addresses: Vec<String>,
+    /// Every TXT record property. Flags without a value are `None`, and values that aren't
+    /// valid UTF-8 are decoded lossily.
+    pub txt: BTreeMap<String, Option<String>>,
+    /// The time in milliseconds from the start of the scan when the instance was last resolved.
+    pub resolved_at_ms: u128,
+}
+
+/// Why a scan ended.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScanEndReason {
+    /// No scan has ended yet.
+    #[default]
+    None,
+    /// The scan duration elapsed.
+    Timeout,
+    /// The scan was stopped by a `stop_scan` call.
+    User,
+    /// The scan could not run, e.g. because the mDNS daemon failed to start.
+    Error,
+    /// The network went quiet during `scan_until_quiet`.
+    Quiet,
+    /// The scan reached the `max_lifetime_secs` of its configuration.
+    MaxLifetime,
+    /// The scan duration elapsed after the scan reached the `max_devices` cap of its
+    /// configuration, so devices may be missing from the list.
+    Count,
+}
+
+/// A message streamed over the channel of a scan started with `start_scan_with_channel`.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum ScanStreamMessage {
+    /// A device was found or updated, with its latest state.
+    Device(Device),
+    /// The scan ended, for the given reason. Nothing is sent after it.
+    Finished(ScanEndReason),
+}
+
+/// Counters accumulated across the scans of the app's lifetime, returned by `get_scan_stats`.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStats {
+    /// The number of scans that started.
+    pub scans_started: u64,
+    /// The number of devices added to the device list, counting a device again each time it is
+    /// rediscovered after being cleared or removed.
+    pub devices_discovered: u64,
+    /// How long scans have run in total, in milliseconds, including the running scan.
+    pub total_scan_time_ms: u128,
+}
+
+/// Aggregates of `discovery_time_ms` across the known devices, returned by
+/// `get_discovery_latency_stats`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryLatencyStats {
+    /// The number of devices the aggregates cover.
+    pub device_count: usize,
+    /// The lowest `discovery_time_ms`.
+    pub min_ms: u128,
+    /// The highest `discovery_time_ms`.
+    pub max_ms: u128,
+    /// The mean `discovery_time_ms`.
+    pub mean_ms: f64,
+    /// The median `discovery_time_ms`, by the nearest-rank method.
+    pub p50_ms: u128,
+    /// The 95th percentile of `discovery_time_ms`, by the nearest-rank method.
+    pub p95_ms: u128,
+}
+
+impl DiscoveryLatencyStats {
+    /// Aggregates the discovery times of the given devices, or returns `None` if there are none.
+    pub fn new<'a>(devices: impl IntoIterator<Item = &'a Device>) -> Option<Self> {
+        let mut times: Vec<u128> = devices
+            .into_iter()
+            .map(|device| device.discovery_time_ms)
+            .collect();
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_unstable();
+        let percentile = |p: usize| times[(p * times.len()).div_ceil(100).max(1) - 1];
+        Some(DiscoveryLatencyStats {
+            device_count: times.len(),
+            min_ms: times[0],
+            max_ms: times[times.len() - 1],
+            mean_ms: times.iter().sum::<u128>() as f64 / times.len() as f64,
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+        })
+    }
+}
+
+/// The payload of the `scan-complete` event, summarizing a whole scan.
+///
+/// Also kept for `get_last_scan_report` when the scan ends for another reason.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReport {
+    /// The number of devices discovered.
+    pub device_count: usize,
+    /// The number of services discovered, across all devices.
+    pub service_count: usize,
+    /// The number of devices with at least one service of each device type, keyed by
+    /// `DeviceType::label`.
+    pub devices_per_type: HashMap<String, usize>,
+    /// The number of devices exposing each service type.
+    pub devices_per_service_type: HashMap<String, usize>,
+    /// The lowest `discovery_time_ms` among the devices, or `None` if none was discovered.
+    pub fastest_discovery_ms: Option<u128>,
+    /// The highest `discovery_time_ms` among the devices, or `None` if none was discovered.
+    pub slowest_discovery_ms: Option<u128>,
+    /// How long the scan ran, in milliseconds.
+    pub duration_ms: u128,
+    /// Why the scan ended.
+    pub reason: ScanEndReason,
+}
+
+impl ScanReport {
+    /// Summarizes the devices of a scan that ran for `duration_ms` and ended for `reason`.
+    pub fn new<'a>(
+        devices: impl IntoIterator<Item = &'a Device>,
+        duration_ms: u128,
+        reason: ScanEndReason,
+    ) -> Self {
+        let mut report = ScanReport {
+            duration_ms,
+            reason,
+            ..Default::default()
+        };
+        for device in devices {
+            report.device_count += 1;
+            report.service_count += device.services.len();
+            let mut types: Vec<&DeviceType> = Vec::new();
+            let mut service_types: Vec<&str> = Vec::new();
+            for service in &device.services {
+                if !types.contains(&&service.device_type) {
+                    types.push(&service.device_type);
+                }
+                if !service_types.contains(&service.service_type.as_str()) {
+                    service_types.push(&service.service_type);
+                }
+            }
+            for device_type in types {
+                *report
+                    .devices_per_type
+                    .entry(device_type.label().to_string())
+                    .or_default() += 1;
+            }
+            for service_type in service_types {
+                *report
+                    .devices_per_service_type
+                    .entry(service_type.to_string())
+                    .or_default() += 1;
+            }
+            report.fastest_discovery_ms = Some(
+                report
+                    .fastest_discovery_ms
+                    .map_or(device.discovery_time_ms, |ms| {
+                        ms.min(device.discovery_time_ms)
+                    }),
+            );
+            report.slowest_discovery_ms = Some(
+                report
+                    .slowest_discovery_ms
+                    .map_or(device.discovery_time_ms, |ms| {
+                        ms.max(device.discovery_time_ms)
+                    }),
+            );
+        }
+        report
+    }
+}
 
 /// Represents a device discovered on the local network.
 #[derive(Serialize, Debug, Clone)]
@@ -6,43 +512,216 @@ use serde::Serialize;
 pub struct Device {
     /// The advertised name of the device.
     pub name: String,
-    /// The IP address of the device.
+    /// The IP address of the device, preferring globally routable addresses over private ones and
+    /// those over link-local ones, then IPv4 over IPv6.
     pub ip: String,
     /// The time in milliseconds from the start of the scan until the first service on this device was discovered.
+    ///
+    /// With `preserve_previous`, this is the lowest such time across the merged scans.
     pub discovery_time_ms: u128,
+    /// The resolvable hostname of the device's latest service, e.g. `volumio.local.`, kept as
+    /// advertised even with `clean_names`. Empty if the service advertised none.
+    pub hostname: String,
+    /// The hostnames this device was advertised under, cleaned with `clean_names`.
+    pub hostnames: Vec<String>,
+    /// The usable IP addresses this device advertised.
+    pub addresses: Vec<String>,
+    /// The usable IP addresses other than `ip`, in the same order of preference.
+    pub all_ips: Vec<String>,
+    /// The IPs of devices with the same name merged into this one. Only filled by scans
+    /// started with `merge_by_name`.
+    pub additional_ips: Vec<String>,
     /// A list of mDNS services discovered on this device.
     pub services: Vec<DiscoveredService>,
+    /// User-assigned tags, kept as the device's services are refreshed.
+    pub tags: Vec<String>,
+    /// A stable identifier for the physical device that survives IP changes, derived from its
+    /// primary hostname, service types and stable TXT keys.
+    pub fingerprint: String,
+    /// Whether the device is currently advertising any service. Only ever `false` for scans
+    /// started with `keep_offline_devices`.
+    pub online: bool,
+    /// The device's MAC address from the system's ARP table, as lowercase colon-separated
+    /// octets. Only looked up for scans started with `resolve_mac`, and `None` until found.
+    pub mac: Option<String>,
+    /// The vendor registered for the OUI prefix of `mac`, from an embedded table of common
+    /// vendors. `None` until the MAC is found, and for prefixes the table doesn't know.
+    pub vendor: Option<String>,
+    /// The stable TXT record keys seen across the device's services, lowercased and sorted.
+    #[serde(skip)]
+    pub txt_keys: Vec<String>,
+    /// When a service on the device was last resolved, used to prune stale devices.
+    #[serde(skip)]
+    pub last_seen_at: Instant,
+}
+
+/// TXT keys whose presence varies between announcements, so they are left out of fingerprints.
+const VOLATILE_TXT_KEYS: &[&str] = &[
+    "seq",
+    "ts",
+    "timestamp",
+    "uptime",
+    "state",
+    "status",
+    "vol",
+    "volume",
+];
+
+/// Hashes bytes with 64-bit FNV-1a, which, unlike the standard library's hasher, is guaranteed to
+/// give the same result across builds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Approximates the heap bytes owned by a list of strings.
+fn strings_heap_size(strings: &[String]) -> usize {
+    strings.iter().map(String::capacity).sum()
 }
 
 impl Device {
+    /// Approximates the heap bytes owned by the device, counting string and vector capacities.
+    pub fn heap_size(&self) -> usize {
+        self.name.capacity()
+            + self.ip.capacity()
+            + self.hostname.capacity()
+            + self.hostnames.capacity() * std::mem::size_of::<String>()
+            + strings_heap_size(&self.hostnames)
+            + self.addresses.capacity() * std::mem::size_of::<String>()
+            + strings_heap_size(&self.addresses)
+            + self.all_ips.capacity() * std::mem::size_of::<String>()
+            + strings_heap_size(&self.all_ips)
+            + self.additional_ips.capacity() * std::mem::size_of::<String>()
+            + strings_heap_size(&self.additional_ips)
+            + self.services.capacity() * std::mem::size_of::<DiscoveredService>()
+            + self
+                .services
+                .iter()
+                .map(|s| {
+                    s.service_type.capacity()
+                        + s.display_name.capacity()
+                        + s.fullname.capacity()
+                        + s.txt_properties.capacity()
+                            * (1 + std::mem::size_of::<(String, String)>())
+                        + s.txt_properties
+                            .iter()
+                            .map(|(key, value)| key.capacity() + value.capacity())
+                            .sum::<usize>()
+                })
+                .sum::<usize>()
+            + self.tags.capacity() * std::mem::size_of::<String>()
+            + strings_heap_size(&self.tags)
+            + self.fingerprint.capacity()
+            + self.mac.as_ref().map_or(0, String::capacity)
+            + self.vendor.as_ref().map_or(0, String::capacity)
+            + self.txt_keys.capacity() * std::mem::size_of::<String>()
+            + strings_heap_size(&self.txt_keys)
+    }
+
+    /// Returns whether any of the device's services is classified as the given type.
+    pub fn has_device_type(&self, device_type: &DeviceType) -> bool {
+        self.services.iter().any(|s| &s.device_type == device_type)
+    }
+
+    /// Records a hostname and addresses the device was advertised under, skipping known ones.
+    pub fn add_aliases(&mut self, hostname: &str, addresses: &[String]) {
+        if !hostname.is_empty() && !self.hostnames.iter().any(|h| h == hostname) {
+            self.hostnames.push(hostname.to_string());
+        }
+        for address in addresses {
+            if !self.addresses.contains(address) {
+                self.addresses.push(address.clone());
+            }
+        }
+    }
+
+    /// Records the instance fullname the service of `service_type` was resolved under.
+    pub fn set_service_fullname(&mut self, service_type: &str, fullname: &str) {
+        if let Some(service) = self
+            .services
+            .iter_mut()
+            .find(|s| s.service_type == service_type)
+        {
+            service.fullname = fullname.to_string();
+        }
+    }
+
+    /// Records the TXT keys a service was advertised with, skipping volatile and known ones.
+    pub fn add_txt_keys<'a>(&mut self, keys: impl IntoIterator<Item = &'a str>) {
+        for key in keys {
+            let key = key.to_lowercase();
+            if !VOLATILE_TXT_KEYS.contains(&key.as_str()) && !self.txt_keys.contains(&key) {
+                self.txt_keys.push(key);
+            }
+        }
+        self.txt_keys.sort();
+    }
+
+    /// Recomputes the fingerprint from the primary hostname, the sorted service types and the
+    /// stable TXT keys. The IP address and instance name are deliberately left out.
+    pub fn update_fingerprint(&mut self) {
+        let mut service_types: Vec<&str> = self
+            .services
+            .iter()
+            .map(|s| s.service_type.as_str())
+            .collect();
+        service_types.sort_unstable();
+        let input = format!(
+            "{}\n{}\n{}",
+            self.hostnames
+                .first()
+                .map(|h| h.to_lowercase())
+                .unwrap_or_default(),
+            service_types.join(","),
+            self.txt_keys.join(",")
+        );
+        self.fingerprint = format!("{:016x}", fnv1a(input.as_bytes()));
+    }
+
     /// Adds a new service to the device or updates an existing one.
+    ///
+    /// Returns whether the service is new or its port or device type changed.
     pub fn add_or_update_service(
         &mut self,
         service_type: &str,
         port: u16,
         device_type: DeviceType,
         elapsed_ms: u128,
-    ) {
+        txt_properties: HashMap<String, String>,
+        display_name: String,
+    ) -> bool {
         if let Some(service) = self
             .services
             .iter_mut()
             .find(|s| s.service_type == service_type)
         {
+            let changed = service.port != port || service.device_type != device_type;
             service.port = port;
             service.device_type = device_type;
             service.last_seen_ms = elapsed_ms;
+            service.txt_properties = txt_properties;
+            service.display_name = display_name;
+            changed
         } else {
             self.services.push(DiscoveredService {
                 service_type: service_type.to_string(),
                 port,
                 device_type,
                 last_seen_ms: elapsed_ms,
+                reachable: None,
+                txt_properties,
+                display_name,
+                fullname: String::new(),
             });
+            true
         }
     }
 }
 
 /// Represents a specific mDNS service discovered on a device.
+///
+/// The mDNS library doesn't expose the SRV record's priority and weight, so they aren't included.
 #[derive(Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DiscoveredService {
@@ -54,11 +733,26 @@ pub struct DiscoveredService {
     pub device_type: DeviceType,
     /// The time in milliseconds from the start of the scan when this service was last observed.
     pub last_seen_ms: u128,
+    /// Whether a TCP connection to the service's port succeeded. `None` until checked, and
+    /// only ever checked for scans started with `verify_reachability`.
+    pub reachable: Option<bool>,
+    /// The service's TXT record properties, such as a model or version. Values that aren't valid
+    /// UTF-8 are left out, and properties advertised without a value map to an empty string.
+    pub txt_properties: HashMap<String, String>,
+    /// A human-readable name for the service type, like `Spotify Connect`, for showing to end
+    /// users. The raw service type for types the plugin doesn't know.
+    pub display_name: String,
+    /// The instance fullname the service was last resolved under, e.g.
+    /// `Living Room._spotify-connect._tcp.local.`, as advertised even with `clean_names`.
+    pub fullname: String,
 }
 
 /// The type of device, classified by its discovered mDNS service.
-#[derive(Serialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
+///
+/// Serialized as `{ "kind": "spotifyConnect" }`, or `{ "kind": "custom", "value": "myBrand" }`
+/// for a custom label, so every variant has the same shape.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
 pub enum DeviceType {
     /// A Bluesound device.
     Bluesound,
@@ -68,6 +762,29 @@ pub enum DeviceType {
     SpotifyConnect,
     /// A device with Qobuz Connect.
     QobuzConnect,
+    /// A Roon Ready endpoint.
+    Roon,
+    /// A Chromecast or a device with Chromecast built in.
+    Chromecast,
     /// A generic or unrecognized device.
     Generic,
+    /// A device labeled by a custom classification rule.
+    Custom(String),
+}
+
+impl DeviceType {
+    /// Returns the name keying the type in per-type counts: the `kind` of a built-in type, or the
+    /// label of a custom one.
+    pub fn label(&self) -> &str {
+        match self {
+            DeviceType::Bluesound => "bluesound",
+            DeviceType::Volumio => "volumio",
+            DeviceType::SpotifyConnect => "spotifyConnect",
+            DeviceType::QobuzConnect => "qobuzConnect",
+            DeviceType::Roon => "roon",
+            DeviceType::Chromecast => "chromecast",
+            DeviceType::Generic => "generic",
+            DeviceType::Custom(label) => label,
+        }
+    }
 }
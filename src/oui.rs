@@ -0,0 +1,128 @@
+//! Maps the OUI prefix of a MAC address to the vendor it's registered to.
+//!
+//! The table is embedded rather than loaded, so lookups work offline. It only covers vendors
+//! commonly found advertising on home and studio networks: streamers, speakers, single-board
+//! computers and the network gear around them. Prefixes outside it yield no vendor rather than
+//! a guess.
+
+/// The vendors of known OUI prefixes, sorted by prefix for binary search.
+const OUI_VENDORS: &[([u8; 3], &str)] = &[
+    ([0x00, 0x00, 0xF0], "Samsung Electronics Co.,Ltd"),
+    ([0x00, 0x03, 0x93], "Apple, Inc."),
+    ([0x00, 0x04, 0x20], "Slim Devices, Inc."),
+    ([0x00, 0x04, 0x4B], "NVIDIA"),
+    ([0x00, 0x05, 0xCD], "D&M Holdings Inc."),
+    ([0x00, 0x09, 0x5B], "NETGEAR"),
+    ([0x00, 0x0A, 0x95], "Apple, Inc."),
+    ([0x00, 0x0E, 0x58], "Sonos, Inc."),
+    ([0x00, 0x11, 0x32], "Synology Incorporated"),
+    ([0x00, 0x12, 0xFB], "Samsung Electronics Co.,Ltd"),
+    ([0x00, 0x14, 0x6C], "NETGEAR"),
+    ([0x00, 0x17, 0x88], "Philips Lighting BV"),
+    ([0x00, 0x1B, 0x63], "Apple, Inc."),
+    ([0x00, 0x1E, 0xC2], "Apple, Inc."),
+    ([0x00, 0x25, 0x00], "Apple, Inc."),
+    ([0x00, 0xA0, 0xDE], "Yamaha Corporation"),
+    ([0x04, 0x18, 0xD6], "Ubiquiti Networks Inc."),
+    ([0x04, 0x52, 0xC7], "Bose Corporation"),
+    ([0x08, 0xDF, 0x1F], "Bose Corporation"),
+    ([0x14, 0xCC, 0x20], "TP-LINK TECHNOLOGIES CO.,LTD."),
+    ([0x1C, 0xF2, 0x9A], "Google, Inc."),
+    ([0x20, 0xDF, 0xB9], "Google, Inc."),
+    ([0x24, 0x0A, 0xC4], "Espressif Inc."),
+    ([0x24, 0x6F, 0x28], "Espressif Inc."),
+    ([0x24, 0xA4, 0x3C], "Ubiquiti Networks Inc."),
+    ([0x28, 0xCD, 0xC1], "Raspberry Pi Trading Ltd"),
+    ([0x28, 0xCF, 0xE9], "Apple, Inc."),
+    ([0x2C, 0x41, 0xA1], "Bose Corporation"),
+    ([0x30, 0xAE, 0xA4], "Espressif Inc."),
+    ([0x34, 0x7E, 0x5C], "Sonos, Inc."),
+    ([0x3C, 0x07, 0x54], "Apple, Inc."),
+    ([0x3C, 0x5A, 0xB4], "Google, Inc."),
+    ([0x3C, 0x71, 0xBF], "Espressif Inc."),
+    ([0x44, 0x65, 0x0D], "Amazon Technologies Inc."),
+    ([0x44, 0xD9, 0xE7], "Ubiquiti Networks Inc."),
+    ([0x48, 0xA6, 0xB8], "Sonos, Inc."),
+    ([0x48, 0xD6, 0xD5], "Google, Inc."),
+    ([0x4C, 0x87, 0x5D], "Bose Corporation"),
+    ([0x50, 0xC7, 0xBF], "TP-LINK TECHNOLOGIES CO.,LTD."),
+    ([0x54, 0x2A, 0x1B], "Sonos, Inc."),
+    ([0x54, 0x60, 0x09], "Google, Inc."),
+    ([0x5C, 0xAA, 0xFD], "Sonos, Inc."),
+    ([0x68, 0x37, 0xE9], "Amazon Technologies Inc."),
+    ([0x68, 0x72, 0x51], "Ubiquiti Networks Inc."),
+    ([0x74, 0xC2, 0x46], "Amazon Technologies Inc."),
+    ([0x78, 0x28, 0xCA], "Sonos, Inc."),
+    ([0x78, 0x8A, 0x20], "Ubiquiti Networks Inc."),
+    ([0x80, 0x2A, 0xA8], "Ubiquiti Networks Inc."),
+    ([0x84, 0xD6, 0xD0], "Amazon Technologies Inc."),
+    ([0x84, 0xF3, 0xEB], "Espressif Inc."),
+    ([0x94, 0x9F, 0x3E], "Sonos, Inc."),
+    ([0x98, 0xDA, 0xC4], "TP-LINK TECHNOLOGIES CO.,LTD."),
+    ([0xA4, 0x5E, 0x60], "Apple, Inc."),
+    ([0xA4, 0x77, 0x33], "Google, Inc."),
+    ([0xA4, 0xCF, 0x12], "Espressif Inc."),
+    ([0xAC, 0xBC, 0x32], "Apple, Inc."),
+    ([0xB0, 0xA7, 0x37], "Roku, Inc."),
+    ([0xB8, 0x27, 0xEB], "Raspberry Pi Foundation"),
+    ([0xB8, 0xE9, 0x37], "Sonos, Inc."),
+    ([0xBC, 0xDD, 0xC2], "Espressif Inc."),
+    ([0xCC, 0x50, 0xE3], "Espressif Inc."),
+    ([0xCC, 0x6D, 0xA0], "Roku, Inc."),
+    ([0xD8, 0x3A, 0xDD], "Raspberry Pi Trading Ltd"),
+    ([0xD8, 0x6C, 0x63], "Google, Inc."),
+    ([0xDC, 0x3A, 0x5E], "Roku, Inc."),
+    ([0xDC, 0x9F, 0xDB], "Ubiquiti Networks Inc."),
+    ([0xDC, 0xA6, 0x32], "Raspberry Pi Trading Ltd"),
+    ([0xE4, 0x5F, 0x01], "Raspberry Pi Trading Ltd"),
+    ([0xF0, 0x18, 0x98], "Apple, Inc."),
+    ([0xF0, 0x27, 0x2D], "Amazon Technologies Inc."),
+    ([0xF0, 0x9F, 0xC2], "Ubiquiti Networks Inc."),
+    ([0xF0, 0xF6, 0xC1], "Sonos, Inc."),
+    ([0xF4, 0xF5, 0xD8], "Google, Inc."),
+    ([0xF4, 0xF5, 0xE8], "Google, Inc."),
+    ([0xFC, 0x65, 0xDE], "Amazon Technologies Inc."),
+    ([0xFC, 0xEC, 0xDA], "Ubiquiti Networks Inc."),
+];
+
+/// Returns the vendor registered for the OUI prefix of `mac`, a MAC address formatted like
+/// `aa:bb:cc:dd:ee:ff`, or `None` if the prefix is unknown.
+///
+/// Locally administered addresses, like the randomized ones phones use for privacy, carry no
+/// vendor and always yield `None`.
+pub fn lookup_vendor(mac: &str) -> Option<&'static str> {
+    let mut octets = mac
+        .split(':')
+        .map(|octet| u8::from_str_radix(octet, 16).ok());
+    let prefix = [octets.next()??, octets.next()??, octets.next()??];
+    if prefix[0] & 0x02 != 0 {
+        return None;
+    }
+    OUI_VENDORS
+        .binary_search_by_key(&prefix, |&(oui, _)| oui)
+        .ok()
+        .map(|index| OUI_VENDORS[index].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lookup_vendor, OUI_VENDORS};
+
+    #[test]
+    fn table_is_sorted_for_binary_search() {
+        assert!(OUI_VENDORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn looks_up_vendor_by_prefix() {
+        assert_eq!(lookup_vendor("00:0e:58:12:34:56"), Some("Sonos, Inc."));
+        assert_eq!(
+            lookup_vendor("b8:27:eb:00:00:01"),
+            Some("Raspberry Pi Foundation")
+        );
+        assert_eq!(lookup_vendor("00:00:01:12:34:56"), None);
+        // Locally administered, even though the rest matches Sonos.
+        assert_eq!(lookup_vendor("02:0e:58:12:34:56"), None);
+        assert_eq!(lookup_vendor("not a mac"), None);
+    }
+}
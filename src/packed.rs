@@ -0,0 +1,73 @@
+//! A compact binary encoding of the device list for integrators who measured JSON as a bottleneck.
+//!
+//! All integers are little-endian. Strings are a `u16` byte length followed by UTF-8 bytes.
+//! Times are milliseconds, saturated to `u64`.
+//!
+//! ```text
+//! u32 device_count
+//! device_count x {
+//!     string name
+//!     string ip
+//!     u64    discovery_time_ms
+//!     u16    service_count
+//!     service_count x {
+//!         string service_type
+//!         u16    port
+//!         u8     device_type   (see `device_type_tag`)
+//!         string label         (only when device_type is 5, `Custom`)
+//!         u64    last_seen_ms
+//!     }
+//! }
+//! ```
+//!
+//! Only the fields above are encoded; use the JSON commands when you need the rest.
+
+use super::models::{Device, DeviceType};
+
+/// Returns the tag identifying a `DeviceType` in the packed encoding.
+fn device_type_tag(device_type: &DeviceType) -> u8 {
+    match device_type {
+        DeviceType::Bluesound => 0,
+        DeviceType::Volumio => 1,
+        DeviceType::SpotifyConnect => 2,
+        DeviceType::QobuzConnect => 3,
+        DeviceType::Generic => 4,
+        DeviceType::Custom(_) => 5,
+        DeviceType::Roon => 6,
+        DeviceType::Chromecast => 7,
+    }
+}
+
+fn write_str(buf: &mut Vec<u8>, value: &str) {
+    let len = value.len().min(u16::MAX as usize);
+    buf.extend_from_slice(&(len as u16).to_le_bytes());
+    buf.extend_from_slice(&value.as_bytes()[..len]);
+}
+
+fn write_ms(buf: &mut Vec<u8>, value: u128) {
+    let value = u64::try_from(value).unwrap_or(u64::MAX);
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Encodes the devices into the packed binary format described in the module docs.
+pub fn pack_devices<'a>(devices: impl ExactSizeIterator<Item = &'a Device>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(devices.len() as u32).to_le_bytes());
+    for device in devices {
+        write_str(&mut buf, &device.name);
+        write_str(&mut buf, &device.ip);
+        write_ms(&mut buf, device.discovery_time_ms);
+        let services = &device.services[..device.services.len().min(u16::MAX as usize)];
+        buf.extend_from_slice(&(services.len() as u16).to_le_bytes());
+        for service in services {
+            write_str(&mut buf, &service.service_type);
+            buf.extend_from_slice(&service.port.to_le_bytes());
+            buf.push(device_type_tag(&service.device_type));
+            if let DeviceType::Custom(label) = &service.device_type {
+                write_str(&mut buf, label);
+            }
+            write_ms(&mut buf, service.last_seen_ms);
+        }
+    }
+    buf
+}